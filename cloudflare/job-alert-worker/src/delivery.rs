@@ -0,0 +1,309 @@
+use std::{future::Future, time::Duration};
+
+use base64::{engine::general_purpose, Engine as _};
+use hmac::{Hmac, Mac};
+use serde_json::json;
+use sha2::Sha256;
+
+const BASE_BACKOFF: Duration = Duration::from_millis(500);
+const MAX_BACKOFF: Duration = Duration::from_secs(8);
+
+/// Signs `<timestamp>.<body>` the same way the server's `verify_worker_signature` checks it
+pub fn sign(secret: &str, timestamp: u64, body: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC can take key of any size");
+    mac.update(format!("{}.{}", timestamp, body).as_bytes());
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Body the server deserializes as `EmailWebhookData`
+pub fn build_payload(from: &str, to: &str, raw: &[u8], size: usize) -> String {
+    json!({
+        "from": from,
+        "to": to,
+        "raw_content": general_purpose::STANDARD.encode(raw),
+        "size": size
+    })
+    .to_string()
+}
+
+/// Cheap sanity check so obviously broken mail is bounced instead of retried forever.
+pub fn check_raw_email(raw: &[u8]) -> Result<(), String> {
+    if raw.is_empty() {
+        return Err("empty message".to_string());
+    }
+
+    let header_end = find(raw, b"\r\n\r\n")
+        .or_else(|| find(raw, b"\n\n"))
+        .ok_or_else(|| "message has no header section".to_string())?;
+
+    let headers = String::from_utf8_lossy(&raw[..header_end]).to_lowercase();
+    if !headers.lines().any(|line| line.starts_with("from:")) {
+        return Err("message has no From header".to_string());
+    }
+
+    Ok(())
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+/// Exponential backoff before the retry following `attempt` (1-based).
+pub fn backoff_delay(attempt: u32) -> Duration {
+    BASE_BACKOFF
+        .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+        .min(MAX_BACKOFF)
+}
+
+/// Whether a non-2xx status could go away by itself. Anything else (bad signature,
+/// bad payload) will fail the same way on every attempt.
+pub fn is_retryable(status: u16) -> bool {
+    status >= 500 || status == 408 || status == 429
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Delivery {
+    Delivered { url: String, attempts: u32 },
+    Failed { attempts: u32, error: String },
+}
+
+/// Tries every url in order, retrying the whole list with backoff while the failures
+/// look temporary. `send` returns the response status or a transport error, and `sleep`
+/// is passed in so this can run outside the workers runtime.
+pub async fn deliver<S, SF, D, DF>(
+    urls: &[String],
+    max_attempts: u32,
+    mut send: S,
+    mut sleep: D,
+) -> Delivery
+where
+    S: FnMut(&str) -> SF,
+    SF: Future<Output = Result<u16, String>>,
+    D: FnMut(Duration) -> DF,
+    DF: Future<Output = ()>,
+{
+    let mut error = "no webhook urls configured".to_string();
+    let mut attempts = 0;
+
+    while attempts < max_attempts {
+        attempts += 1;
+        let mut retryable = false;
+
+        for url in urls {
+            match send(url).await {
+                Ok(status) if (200..300).contains(&status) => {
+                    return Delivery::Delivered {
+                        url: url.clone(),
+                        attempts,
+                    }
+                }
+                Ok(status) => {
+                    error = format!("{} answered with status {}", url, status);
+                    retryable |= is_retryable(status);
+                }
+                Err(e) => {
+                    error = format!("{} failed: {}", url, e);
+                    retryable = true;
+                }
+            }
+        }
+
+        if !retryable {
+            break;
+        }
+        if attempts < max_attempts {
+            sleep(backoff_delay(attempts)).await;
+        }
+    }
+
+    Delivery::Failed { attempts, error }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::VecDeque,
+        future::{ready, Future},
+        pin::pin,
+        task::{Context, Poll, Waker},
+    };
+
+    use super::*;
+
+    /// The fake send and sleep never wait, so one poll runs `deliver` to the end.
+    fn block_on<F: Future>(future: F) -> F::Output {
+        match pin!(future).poll(&mut Context::from_waker(Waker::noop())) {
+            Poll::Ready(output) => output,
+            Poll::Pending => panic!("future wasn't ready"),
+        }
+    }
+
+    /// Delivers with `responses` answered in order, returning the urls sent to
+    /// and the delays slept.
+    fn run(
+        urls: &[&str],
+        max_attempts: u32,
+        responses: Vec<Result<u16, String>>,
+    ) -> (Delivery, Vec<String>, Vec<Duration>) {
+        let urls: Vec<String> = urls.iter().map(|url| url.to_string()).collect();
+        let mut responses = VecDeque::from(responses);
+        let mut sent = Vec::new();
+        let mut slept = Vec::new();
+
+        let delivery = block_on(deliver(
+            &urls,
+            max_attempts,
+            |url| {
+                sent.push(url.to_string());
+                ready(responses.pop_front().expect("more sends than responses"))
+            },
+            |delay| {
+                slept.push(delay);
+                ready(())
+            },
+        ));
+        (delivery, sent, slept)
+    }
+
+    #[test]
+    fn retries_server_errors_with_backoff() {
+        let (delivery, sent, slept) = run(&["https://a"], 3, vec![Ok(502), Ok(503), Ok(200)]);
+        assert_eq!(
+            delivery,
+            Delivery::Delivered {
+                url: "https://a".to_string(),
+                attempts: 3
+            }
+        );
+        assert_eq!(sent.len(), 3);
+        assert_eq!(
+            slept,
+            vec![Duration::from_millis(500), Duration::from_secs(1)]
+        );
+    }
+
+    #[test]
+    fn retries_transport_errors() {
+        let (delivery, _, slept) = run(&["https://a"], 2, vec![Err("timed out".into()), Ok(204)]);
+        assert!(matches!(delivery, Delivery::Delivered { attempts: 2, .. }));
+        assert_eq!(slept, vec![Duration::from_millis(500)]);
+    }
+
+    #[test]
+    fn stops_on_client_errors() {
+        let (delivery, sent, slept) = run(&["https://a"], 3, vec![Ok(401)]);
+        assert_eq!(
+            delivery,
+            Delivery::Failed {
+                attempts: 1,
+                error: "https://a answered with status 401".to_string()
+            }
+        );
+        assert_eq!(sent.len(), 1);
+        assert!(slept.is_empty());
+    }
+
+    #[test]
+    fn tries_the_next_url_in_the_same_attempt() {
+        let (delivery, sent, slept) = run(&["https://a", "https://b"], 3, vec![Ok(500), Ok(200)]);
+        assert_eq!(
+            delivery,
+            Delivery::Delivered {
+                url: "https://b".to_string(),
+                attempts: 1
+            }
+        );
+        assert_eq!(sent, vec!["https://a", "https://b"]);
+        assert!(slept.is_empty());
+    }
+
+    #[test]
+    fn fails_after_every_attempt() {
+        let (delivery, sent, slept) = run(
+            &["https://a", "https://b"],
+            2,
+            vec![
+                Ok(500),
+                Err("timed out".into()),
+                Ok(500),
+                Err("timed out".into()),
+            ],
+        );
+        assert_eq!(
+            delivery,
+            Delivery::Failed {
+                attempts: 2,
+                error: "https://b failed: timed out".to_string()
+            }
+        );
+        assert_eq!(
+            sent,
+            vec!["https://a", "https://b", "https://a", "https://b"]
+        );
+        // no wait after the last attempt, the email is forwarded right away
+        assert_eq!(slept, vec![Duration::from_millis(500)]);
+    }
+
+    #[test]
+    fn fails_without_urls() {
+        let (delivery, sent, _) = run(&[], 3, vec![]);
+        assert_eq!(
+            delivery,
+            Delivery::Failed {
+                attempts: 1,
+                error: "no webhook urls configured".to_string()
+            }
+        );
+        assert!(sent.is_empty());
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_the_cap() {
+        assert_eq!(backoff_delay(0), Duration::from_millis(500));
+        assert_eq!(backoff_delay(1), Duration::from_millis(500));
+        assert_eq!(backoff_delay(2), Duration::from_secs(1));
+        assert_eq!(backoff_delay(3), Duration::from_secs(2));
+        assert_eq!(backoff_delay(5), Duration::from_secs(8));
+        assert_eq!(backoff_delay(6), Duration::from_secs(8));
+        assert_eq!(backoff_delay(u32::MAX), Duration::from_secs(8));
+    }
+
+    #[test]
+    fn retryable_statuses() {
+        for status in [408, 429, 500, 502, 503, 504] {
+            assert!(is_retryable(status), "{}", status);
+        }
+        for status in [400, 401, 403, 404, 413, 422] {
+            assert!(!is_retryable(status), "{}", status);
+        }
+    }
+
+    #[test]
+    fn signs_timestamp_and_body() {
+        // python3 -c "import hmac, hashlib; print(hmac.new(b'whsec_test',
+        //   b'1700000000.{\"from\":\"alerts@example.com\"}', hashlib.sha256).hexdigest())"
+        assert_eq!(
+            sign("whsec_test", 1700000000, r#"{"from":"alerts@example.com"}"#),
+            "sha256=0f0b1aff7872189e5abcf2ba800ad6076fdfa304074a25fac40ee67203b5332d"
+        );
+    }
+
+    #[test]
+    fn checks_raw_email() {
+        assert!(check_raw_email(b"From: a@b.c\r\nSubject: hi\r\n\r\nbody").is_ok());
+        assert!(check_raw_email(b"Subject: hi\nFROM: a@b.c\n\nbody").is_ok());
+
+        assert_eq!(check_raw_email(b""), Err("empty message".to_string()));
+        assert_eq!(
+            check_raw_email(b"From: a@b.c\r\nSubject: hi"),
+            Err("message has no header section".to_string())
+        );
+        assert_eq!(
+            check_raw_email(b"Subject: hi\r\n\r\nFrom: a@b.c"),
+            Err("message has no From header".to_string())
+        );
+    }
+}
@@ -1,32 +1,92 @@
+use std::time::Duration;
+
 use worker::*;
-use serde_json::json;
-use base64::{Engine as _, engine::general_purpose};
+
+mod delivery;
+mod settings;
+
+use delivery::Delivery;
+use settings::Settings;
 
 #[event(email)]
-async fn main(message: EmailMessage, _env: Env, _ctx: Context) -> Result<()> {
+async fn main(message: EmailMessage, env: Env, _ctx: Context) -> Result<()> {
     console_error_panic_hook::set_once();
-    console_log!("Received email, forwarding and sending webhook");
-    // message.forward("arnav@dvnl.work".to_string(), None).await?;
+    console_log!("Received email from {}", message.from_email());
 
-    // let webhook_url = "https://hooks.dvnl.work/cloudflare-job-alert-reciever";
-    let webhook_url = "https://rust-webhooks-wxht.shuttle.app/cloudflare-job-alert-reciever";
-    console_log!("Attempting to send webhook to: {}", webhook_url);
+    let settings = Settings::from_lookup(|name| {
+        env.secret(name)
+            .or_else(|_| env.var(name))
+            .ok()
+            .map(|value| value.to_string())
+    })
+    .map_err(|e| Error::RustError(format!("Invalid worker settings: {}", e)))?;
 
-    let email_data = json!({
-        "from": message.from_email(),
-        "to": message.to_email(),
-        "raw_content": general_purpose::STANDARD.encode(message.raw_bytes().await.unwrap()),
-        "size": message.raw_size()
-    });
+    let raw = match message.raw_bytes().await {
+        Ok(raw) => raw,
+        Err(e) => {
+            console_error!("Failed to read the raw email: {}", e);
+            message.set_reject("Could not read message".to_string())?;
+            return Ok(());
+        }
+    };
+    if let Err(reason) = delivery::check_raw_email(&raw) {
+        console_warn!("Rejecting malformed email: {}", reason);
+        message.set_reject(format!("Malformed message: {}", reason))?;
+        return Ok(());
+    }
+
+    let body = delivery::build_payload(
+        &message.from_email(),
+        &message.to_email(),
+        &raw,
+        message.raw_size(),
+    );
 
     let client = reqwest::Client::new();
-    let _res = client
-        .post(webhook_url)
-        .header("content-type", "application/json")
-        .body(email_data.to_string())
-        .send()
-        .await
-        .unwrap();
+    let send = |url: &str| {
+        // signed per attempt so retries don't go stale on the server
+        let timestamp = Date::now().as_millis() / 1000;
+        let request = client
+            .post(url)
+            .header("content-type", "application/json")
+            .header("x-webhook-timestamp", timestamp.to_string())
+            .header(
+                "x-webhook-signature",
+                delivery::sign(&settings.secret, timestamp, &body),
+            )
+            .body(body.clone());
+        console_log!("Sending webhook to: {}", url);
+        async move {
+            request
+                .send()
+                .await
+                .map(|response| response.status().as_u16())
+                .map_err(|e| e.to_string())
+        }
+    };
+    let sleep = |delay: Duration| Delay::from(delay);
+
+    match delivery::deliver(&settings.webhook_urls, settings.max_attempts, send, sleep).await {
+        Delivery::Delivered { url, attempts } => {
+            console_log!("Webhook accepted by {} after {} attempt(s)", url, attempts);
+        }
+        Delivery::Failed { attempts, error } => {
+            console_error!("Webhook failed after {} attempt(s): {}", attempts, error);
+            match &settings.forward_to {
+                Some(forward_to) => {
+                    console_log!("Forwarding email to {} instead", forward_to);
+                    message.forward(forward_to.clone(), None).await?;
+                }
+                None => {
+                    // failing the event makes cloudflare bounce it back to the sender
+                    return Err(Error::RustError(format!(
+                        "Webhook failed and FORWARD_TO is not set: {}",
+                        error
+                    )));
+                }
+            }
+        }
+    }
 
     Ok(())
 }
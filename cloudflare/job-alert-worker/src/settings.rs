@@ -0,0 +1,183 @@
+use std::fmt;
+
+pub const DEFAULT_MAX_ATTEMPTS: u32 = 3;
+
+/// Worker settings, read from the wrangler vars and secrets.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Settings {
+    /// Webhook endpoints, tried in order on every attempt
+    pub webhook_urls: Vec<String>,
+    /// Mailbox to forward the email to when no webhook accepted it
+    pub forward_to: Option<String>,
+    pub secret: String,
+    pub max_attempts: u32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SettingsError {
+    Missing(&'static str),
+    Invalid(&'static str, String),
+}
+
+impl fmt::Display for SettingsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SettingsError::Missing(name) => write!(f, "{} is not set", name),
+            SettingsError::Invalid(name, value) => {
+                write!(f, "{} has an invalid value '{}'", name, value)
+            }
+        }
+    }
+}
+
+impl Settings {
+    /// `lookup` returns the value of a var or secret by name, kept as a closure so
+    /// this doesn't depend on the worker `Env` and can run natively.
+    pub fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Result<Self, SettingsError> {
+        let webhook_urls: Vec<String> = lookup("WEBHOOK_URLS")
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|url| !url.is_empty())
+            .map(str::to_string)
+            .collect();
+        if webhook_urls.is_empty() {
+            return Err(SettingsError::Missing("WEBHOOK_URLS"));
+        }
+        if let Some(url) = webhook_urls
+            .iter()
+            .find(|url| !url.starts_with("https://") && !url.starts_with("http://"))
+        {
+            return Err(SettingsError::Invalid("WEBHOOK_URLS", url.clone()));
+        }
+
+        let forward_to = lookup("FORWARD_TO")
+            .map(|address| address.trim().to_string())
+            .filter(|address| !address.is_empty());
+
+        let secret = lookup("WEBHOOK_SECRET")
+            .filter(|secret| !secret.is_empty())
+            .ok_or(SettingsError::Missing("WEBHOOK_SECRET"))?;
+
+        let max_attempts = match lookup("MAX_ATTEMPTS") {
+            None => DEFAULT_MAX_ATTEMPTS,
+            Some(value) => match value.trim().parse::<u32>() {
+                Ok(attempts) if attempts > 0 => attempts,
+                _ => return Err(SettingsError::Invalid("MAX_ATTEMPTS", value)),
+            },
+        };
+
+        Ok(Self {
+            webhook_urls,
+            forward_to,
+            secret,
+            max_attempts,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lookup<'a>(vars: &'a [(&'a str, &'a str)]) -> impl Fn(&str) -> Option<String> + 'a {
+        move |name| {
+            vars.iter()
+                .find(|(key, _)| *key == name)
+                .map(|(_, value)| value.to_string())
+        }
+    }
+
+    #[test]
+    fn reads_every_setting() {
+        let settings = Settings::from_lookup(lookup(&[
+            (
+                "WEBHOOK_URLS",
+                " https://a.example/hook , http://b.example/hook,",
+            ),
+            ("FORWARD_TO", " me@example.com "),
+            ("WEBHOOK_SECRET", "whsec_test"),
+            ("MAX_ATTEMPTS", " 5 "),
+        ]));
+        assert_eq!(
+            settings,
+            Ok(Settings {
+                webhook_urls: vec![
+                    "https://a.example/hook".to_string(),
+                    "http://b.example/hook".to_string()
+                ],
+                forward_to: Some("me@example.com".to_string()),
+                secret: "whsec_test".to_string(),
+                max_attempts: 5,
+            })
+        );
+    }
+
+    #[test]
+    fn defaults_the_optional_settings() {
+        let settings = Settings::from_lookup(lookup(&[
+            ("WEBHOOK_URLS", "https://a.example/hook"),
+            ("FORWARD_TO", "  "),
+            ("WEBHOOK_SECRET", "whsec_test"),
+        ]))
+        .unwrap();
+        assert_eq!(settings.forward_to, None);
+        assert_eq!(settings.max_attempts, DEFAULT_MAX_ATTEMPTS);
+    }
+
+    #[test]
+    fn needs_webhook_urls() {
+        for urls in [None, Some(""), Some(" , ,")] {
+            let mut vars = vec![("WEBHOOK_SECRET", "whsec_test")];
+            if let Some(urls) = urls {
+                vars.push(("WEBHOOK_URLS", urls));
+            }
+            assert_eq!(
+                Settings::from_lookup(lookup(&vars)),
+                Err(SettingsError::Missing("WEBHOOK_URLS"))
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_urls_without_a_scheme() {
+        assert_eq!(
+            Settings::from_lookup(lookup(&[
+                ("WEBHOOK_URLS", "https://a.example/hook,b.example/hook"),
+                ("WEBHOOK_SECRET", "whsec_test"),
+            ])),
+            Err(SettingsError::Invalid(
+                "WEBHOOK_URLS",
+                "b.example/hook".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn needs_a_secret() {
+        for secret in [None, Some("")] {
+            let mut vars = vec![("WEBHOOK_URLS", "https://a.example/hook")];
+            if let Some(secret) = secret {
+                vars.push(("WEBHOOK_SECRET", secret));
+            }
+            assert_eq!(
+                Settings::from_lookup(lookup(&vars)),
+                Err(SettingsError::Missing("WEBHOOK_SECRET"))
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_bad_max_attempts() {
+        for value in ["0", "-1", "three", ""] {
+            assert_eq!(
+                Settings::from_lookup(lookup(&[
+                    ("WEBHOOK_URLS", "https://a.example/hook"),
+                    ("WEBHOOK_SECRET", "whsec_test"),
+                    ("MAX_ATTEMPTS", value),
+                ])),
+                Err(SettingsError::Invalid("MAX_ATTEMPTS", value.to_string()))
+            );
+        }
+    }
+}
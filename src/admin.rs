@@ -0,0 +1,427 @@
+use std::{convert::Infallible, sync::Arc};
+
+use anyhow::anyhow;
+use axum::{
+    extract::{Path, Query, Request, State},
+    middleware::{self, Next},
+    response::sse::{Event, KeepAlive, Sse},
+    routing::{get, post, put},
+    Extension, Json, Router,
+};
+use chrono::{Duration, NaiveDate, Utc};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio::sync::mpsc;
+use tokio_stream::{wrappers::UnboundedReceiverStream, Stream, StreamExt};
+
+use crate::{
+    auth::{Principal, Scope},
+    config::NotionSyncConfig,
+    error::AppError,
+    helpers::{
+        archive,
+        cron_history::{self, RunTrigger},
+        error_reporter::ErrorContext,
+        job_store, notion,
+    },
+    middlewares,
+    models::{
+        archive::{ArchivedWebhook, ArchivedWebhookSummary},
+        posting::{ApplicationEvent, ApplicationStatus, StoredPosting},
+        queue::DeadLetter,
+        schedule::CronRun,
+    },
+    queue,
+    scheduler::{self, RunContext},
+    AppData,
+};
+
+pub fn router() -> Router<Arc<AppData>> {
+    let webhooks_read = Router::new()
+        .route("/dead-letters", get(list_dead_letters))
+        .route("/webhooks", get(list_webhooks))
+        .route("/webhooks/{id}", get(get_webhook));
+
+    let webhooks_replay = Router::new()
+        .route("/dead-letters/{id}/replay", post(replay_dead_letter))
+        .route("/webhooks/{id}/replay", post(replay_webhook));
+
+    let jobs_read = Router::new()
+        .route("/schedules", get(list_schedules))
+        .route("/schedules/runs", get(list_runs))
+        .route("/schedules/{name}/runs", get(list_schedule_runs));
+
+    let jobs_trigger = Router::new().route("/schedules/{name}/run", post(trigger_schedule));
+
+    let tokens_issue = Router::new().route("/tokens", post(issue_token));
+
+    // keys have slashes in them, so clients percent-encode them
+    let postings_read = Router::new()
+        .route("/postings", get(list_postings))
+        .route("/postings/{key}", get(get_posting));
+
+    let postings_write = Router::new()
+        .route(
+            "/postings/{key}/saved",
+            put(save_posting).delete(unsave_posting),
+        )
+        .route("/postings/{key}/status", put(set_posting_status));
+
+    Router::new()
+        .merge(scoped(Scope::WebhooksRead, webhooks_read))
+        .merge(scoped(Scope::WebhooksReplay, webhooks_replay))
+        .merge(scoped(Scope::JobsRead, jobs_read))
+        .merge(scoped(Scope::JobsTrigger, jobs_trigger))
+        .merge(scoped(Scope::TokensIssue, tokens_issue))
+        .merge(scoped(Scope::PostingsRead, postings_read))
+        .merge(scoped(Scope::PostingsWrite, postings_write))
+}
+
+fn scoped(scope: Scope, router: Router<Arc<AppData>>) -> Router<Arc<AppData>> {
+    router.route_layer(middleware::from_fn(move |request: Request, next: Next| {
+        middlewares::require_scope(scope, request, next)
+    }))
+}
+
+async fn list_dead_letters(
+    State(state): State<Arc<AppData>>,
+) -> Result<Json<Vec<DeadLetter>>, AppError> {
+    Ok(Json(state.queue.dead_letters().await?))
+}
+
+async fn replay_dead_letter(
+    State(state): State<Arc<AppData>>,
+    Path(id): Path<String>,
+) -> Result<Json<Value>, AppError> {
+    match state.queue.replay_dead_letter(&id).await? {
+        Some(id) => Ok(Json(json!({ "status": "queued", "id": id }))),
+        None => Err(AppError::NotFound(format!("No dead letter with id {}", id))),
+    }
+}
+
+#[derive(Deserialize)]
+struct ListWebhooksQuery {
+    route: Option<String>,
+    limit: Option<i64>,
+    offset: Option<i64>,
+}
+
+async fn list_webhooks(
+    State(state): State<Arc<AppData>>,
+    Query(query): Query<ListWebhooksQuery>,
+) -> Result<Json<Vec<ArchivedWebhookSummary>>, AppError> {
+    Ok(Json(
+        archive::list_webhooks(
+            &state.db,
+            query.route.as_deref(),
+            query.limit.unwrap_or(50).clamp(1, 500),
+            query.offset.unwrap_or(0).max(0),
+        )
+        .await?,
+    ))
+}
+
+async fn get_webhook(
+    State(state): State<Arc<AppData>>,
+    Path(id): Path<String>,
+) -> Result<Json<ArchivedWebhook>, AppError> {
+    archive::get_webhook(&state.db, &id)
+        .await?
+        .map(Json)
+        .ok_or_else(|| AppError::NotFound(format!("No archived webhook with id {}", id)))
+}
+
+#[derive(Deserialize)]
+struct ReplayQuery {
+    #[serde(default)]
+    dry_run: bool,
+}
+
+async fn replay_webhook(
+    State(state): State<Arc<AppData>>,
+    Path(id): Path<String>,
+    Query(query): Query<ReplayQuery>,
+) -> Result<Json<Value>, AppError> {
+    let webhook = archive::get_webhook(&state.db, &id)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("No archived webhook with id {}", id)))?;
+
+    let payload = queue::decode_payload(&webhook.route, webhook.body.as_bytes())?;
+    let result = queue::replay_payload(&state, &payload, query.dry_run).await?;
+
+    Ok(Json(json!({
+        "id": webhook.id,
+        "route": webhook.route,
+        "dry_run": query.dry_run,
+        "result": result,
+    })))
+}
+
+#[derive(Deserialize)]
+struct ListRunsQuery {
+    limit: Option<i64>,
+}
+
+async fn list_runs(
+    State(state): State<Arc<AppData>>,
+    Query(query): Query<ListRunsQuery>,
+) -> Result<Json<Vec<CronRun>>, AppError> {
+    Ok(Json(
+        cron_history::list_runs(&state.db, None, query.limit.unwrap_or(50).clamp(1, 500)).await?,
+    ))
+}
+
+async fn list_schedule_runs(
+    State(state): State<Arc<AppData>>,
+    Path(name): Path<String>,
+    Query(query): Query<ListRunsQuery>,
+) -> Result<Json<Vec<CronRun>>, AppError> {
+    if state.config.schedule(&name).is_none() {
+        return Err(AppError::NotFound(format!("No schedule named {}", name)));
+    }
+
+    Ok(Json(
+        cron_history::list_runs(
+            &state.db,
+            Some(&name),
+            query.limit.unwrap_or(50).clamp(1, 500),
+        )
+        .await?,
+    ))
+}
+
+async fn list_schedules(State(state): State<Arc<AppData>>) -> Result<Json<Vec<Value>>, AppError> {
+    let mut schedules = Vec::with_capacity(state.config.schedules.len());
+    for schedule in &state.config.schedules {
+        let last_run = cron_history::list_runs(&state.db, Some(&schedule.name), 1)
+            .await?
+            .pop();
+        schedules.push(json!({
+            "schedule": schedule,
+            "last_run": last_run,
+        }));
+    }
+    Ok(Json(schedules))
+}
+
+#[derive(Deserialize)]
+struct TriggerQuery {
+    #[serde(default)]
+    dry_run: bool,
+}
+
+/// Runs a schedule right away and streams its progress as server-sent events,
+/// ending with a `result` event once the job is done.
+async fn trigger_schedule(
+    State(state): State<Arc<AppData>>,
+    Path(name): Path<String>,
+    Query(query): Query<TriggerQuery>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, AppError> {
+    let schedule = state
+        .config
+        .schedule(&name)
+        .cloned()
+        .ok_or_else(|| AppError::NotFound(format!("No schedule named {}", name)))?;
+
+    let (progress, progress_rx) = mpsc::unbounded_channel();
+    let (result_tx, result_rx) = mpsc::unbounded_channel();
+    let ctx = RunContext::manual(query.dry_run, progress);
+
+    tokio::spawn(async move {
+        let result =
+            scheduler::run_recorded(&schedule, &state, Utc::now(), RunTrigger::Manual, &ctx).await;
+        let outcome = match result {
+            Ok(()) => json!({ "outcome": "succeeded" }),
+            Err(e) => json!({ "outcome": "failed", "error": format!("{:#}", e) }),
+        };
+        let _ = result_tx.send(outcome);
+    });
+
+    // the progress stream ends when the job drops its sender, then the result follows
+    let logs = UnboundedReceiverStream::new(progress_rx)
+        .map(|message| Ok(Event::default().event("log").data(message)));
+    let result = UnboundedReceiverStream::new(result_rx)
+        .map(|outcome| Ok(Event::default().event("result").data(outcome.to_string())));
+
+    Ok(Sse::new(logs.chain(result)).keep_alive(KeepAlive::default()))
+}
+
+#[derive(Deserialize)]
+struct IssueTokenRequest {
+    name: String,
+    scopes: Vec<Scope>,
+    ttl_secs: i64,
+}
+
+/// Issues a signed short-lived token, which can't carry scopes its issuer doesn't have.
+async fn issue_token(
+    State(state): State<Arc<AppData>>,
+    Extension(issuer): Extension<Principal>,
+    Json(request): Json<IssueTokenRequest>,
+) -> Result<Json<Value>, AppError> {
+    if let Some(scope) = request
+        .scopes
+        .iter()
+        .find(|scope| !issuer.has_scope(**scope))
+    {
+        return Err(AppError::Forbidden(format!(
+            "Can't issue a token with the {} scope",
+            scope
+        )));
+    }
+    if request.ttl_secs <= 0 {
+        return Err(AppError::BadRequest(
+            "ttl_secs must be positive".to_string(),
+        ));
+    }
+
+    let principal = Principal {
+        name: format!("{} (issued by {})", request.name, issuer.name),
+        scopes: request.scopes,
+    };
+    let (token, expires_at) = state
+        .auth
+        .issue(principal, Duration::seconds(request.ttl_secs))?;
+
+    Ok(Json(json!({
+        "token": token,
+        "expires_at": expires_at.to_rfc3339(),
+    })))
+}
+
+#[derive(Deserialize)]
+struct ListPostingsQuery {
+    status: Option<ApplicationStatus>,
+}
+
+/// Postings in the application pipeline, `?status=applied` for just one stage.
+async fn list_postings(
+    State(state): State<Arc<AppData>>,
+    Query(query): Query<ListPostingsQuery>,
+) -> Result<Json<Vec<StoredPosting>>, AppError> {
+    Ok(Json(job_store::by_status(&state.db, query.status).await?))
+}
+
+/// A stored posting with its revision history and application steps.
+async fn get_posting(
+    State(state): State<Arc<AppData>>,
+    Path(key): Path<String>,
+) -> Result<Json<Value>, AppError> {
+    let posting = job_store::get(&state.db, &key)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("No posting with key {}", key)))?;
+    let revisions = job_store::revisions(&state.db, &key).await?;
+    let events = job_store::events(&state.db, &key).await?;
+
+    Ok(Json(json!({
+        "posting": posting,
+        "revisions": revisions,
+        "events": events,
+    })))
+}
+
+async fn save_posting(
+    State(state): State<Arc<AppData>>,
+    Path(key): Path<String>,
+) -> Result<Json<Value>, AppError> {
+    if !save(&state, &key).await? {
+        return Err(AppError::NotFound(format!("No posting with key {}", key)));
+    }
+    Ok(Json(json!({ "key": key, "saved": true })))
+}
+
+async fn unsave_posting(
+    State(state): State<Arc<AppData>>,
+    Path(key): Path<String>,
+) -> Result<Json<Value>, AppError> {
+    if !job_store::unsave(&state.db, &key).await? {
+        return Err(AppError::NotFound(format!("No posting with key {}", key)));
+    }
+    Ok(Json(json!({ "key": key, "saved": false })))
+}
+
+#[derive(Deserialize)]
+struct SetStatusRequest {
+    status: ApplicationStatus,
+    note: Option<String>,
+    /// When it happened, `YYYY-MM-DD`, today when left out
+    date: Option<String>,
+}
+
+async fn set_posting_status(
+    State(state): State<Arc<AppData>>,
+    Path(key): Path<String>,
+    Json(request): Json<SetStatusRequest>,
+) -> Result<Json<ApplicationEvent>, AppError> {
+    let date = match &request.date {
+        Some(date) => date
+            .parse::<NaiveDate>()
+            .map_err(|_| AppError::BadRequest(format!("date must be YYYY-MM-DD, got {}", date)))?,
+        None => Utc::now().date_naive(),
+    };
+
+    update_application(&state, &key, request.status, request.note.as_deref(), date)
+        .await?
+        .map(Json)
+        .ok_or_else(|| AppError::NotFound(format!("No posting with key {}", key)))
+}
+
+/// Saves a posting, false if there's no posting with that key. Saving again
+/// keeps where the application is at.
+pub async fn save(state: &AppData, key: &str) -> Result<bool, anyhow::Error> {
+    let Some(posting) = job_store::get(&state.db, key).await? else {
+        return Ok(false);
+    };
+    if posting.status.is_none() {
+        let today = Utc::now().date_naive();
+        update_application(state, key, ApplicationStatus::Saved, None, today).await?;
+    }
+    Ok(true)
+}
+
+/// Moves a posting along the application pipeline, and mirrors it to the job
+/// alert Notion database when that's set up. A failed sync is reported but
+/// doesn't undo the change. Nothing if there's no posting with that key.
+pub async fn update_application(
+    state: &AppData,
+    key: &str,
+    status: ApplicationStatus,
+    note: Option<&str>,
+    date: NaiveDate,
+) -> Result<Option<ApplicationEvent>, anyhow::Error> {
+    let Some(event) = job_store::set_status(&state.db, key, status, note, date).await? else {
+        return Ok(None);
+    };
+
+    if let Some(config) = &state.config.applications.notion {
+        if let Err(e) = sync_to_notion(state, config, &event).await {
+            state
+                .error_reporter
+                .report_in_background(ErrorContext::job("notion-sync"), format!("{:#}", e));
+        }
+    }
+    Ok(Some(event))
+}
+
+async fn sync_to_notion(
+    state: &AppData,
+    config: &NotionSyncConfig,
+    event: &ApplicationEvent,
+) -> Result<(), anyhow::Error> {
+    let posting = job_store::get(&state.db, &event.key)
+        .await?
+        .ok_or_else(|| anyhow!("posting {} is gone", event.key))?;
+    let page_id = notion::sync_job_application(
+        &state.job_alert.notion_client,
+        &state.job_alert.db_id,
+        config,
+        &posting,
+        event,
+    )
+    .await?;
+
+    if posting.notion_page_id.as_deref() != Some(page_id.as_str()) {
+        job_store::set_notion_page(&state.db, &posting.key, &page_id).await?;
+    }
+    Ok(())
+}
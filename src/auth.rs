@@ -0,0 +1,222 @@
+use std::{fmt, sync::Arc};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use chrono::{DateTime, Duration, Utc};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use subtle::ConstantTimeEq;
+use tracing::{info, warn};
+
+const SIGNED_TOKEN_PREFIX: &str = "v1.";
+
+/// What an admin token is allowed to do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Scope {
+    #[serde(rename = "jobs:read")]
+    JobsRead,
+    #[serde(rename = "jobs:trigger")]
+    JobsTrigger,
+    #[serde(rename = "webhooks:read")]
+    WebhooksRead,
+    #[serde(rename = "webhooks:replay")]
+    WebhooksReplay,
+    #[serde(rename = "tokens:issue")]
+    TokensIssue,
+    #[serde(rename = "postings:read")]
+    PostingsRead,
+    #[serde(rename = "postings:write")]
+    PostingsWrite,
+}
+
+impl fmt::Display for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Scope::JobsRead => "jobs:read",
+            Scope::JobsTrigger => "jobs:trigger",
+            Scope::WebhooksRead => "webhooks:read",
+            Scope::WebhooksReplay => "webhooks:replay",
+            Scope::TokensIssue => "tokens:issue",
+            Scope::PostingsRead => "postings:read",
+            Scope::PostingsWrite => "postings:write",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AuthConfig {
+    #[serde(default)]
+    pub tokens: Vec<StaticTokenConfig>,
+    /// Longest lifetime a signed token can be issued with
+    #[serde(default = "default_max_token_ttl_secs")]
+    pub max_token_ttl_secs: i64,
+}
+
+/// A long-lived token, the value itself is read from the secret named by `secret`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct StaticTokenConfig {
+    pub name: String,
+    pub secret: String,
+    pub scopes: Vec<Scope>,
+}
+
+fn default_max_token_ttl_secs() -> i64 {
+    24 * 60 * 60
+}
+
+/// Who made an authenticated admin request, added to the request extensions.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Principal {
+    pub name: String,
+    pub scopes: Vec<Scope>,
+}
+
+impl Principal {
+    pub fn has_scope(&self, scope: Scope) -> bool {
+        self.scopes.contains(&scope)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SignedClaims {
+    #[serde(flatten)]
+    principal: Principal,
+    exp: i64,
+}
+
+struct StaticToken {
+    token: String,
+    principal: Principal,
+}
+
+#[derive(Clone)]
+pub struct AdminAuth {
+    tokens: Arc<Vec<StaticToken>>,
+    signing_key: Option<String>,
+    max_token_ttl: Duration,
+}
+
+impl AdminAuth {
+    /// `lookup_secret` resolves the secret names from the config to token values.
+    pub fn new(
+        config: &AuthConfig,
+        signing_key: Option<String>,
+        lookup_secret: impl Fn(&str) -> Option<String>,
+    ) -> Self {
+        let tokens: Vec<StaticToken> = config
+            .tokens
+            .iter()
+            .filter_map(|token| match lookup_secret(&token.secret) {
+                Some(value) if !value.is_empty() => Some(StaticToken {
+                    token: value,
+                    principal: Principal {
+                        name: token.name.clone(),
+                        scopes: token.scopes.clone(),
+                    },
+                }),
+                _ => {
+                    warn!(
+                        "Admin token '{}' has no value in secret {}, skipping",
+                        token.name, token.secret
+                    );
+                    None
+                }
+            })
+            .collect();
+
+        info!(
+            "Admin auth configured with {} static token(s), signed tokens {}",
+            tokens.len(),
+            if signing_key.is_some() {
+                "enabled"
+            } else {
+                "disabled"
+            }
+        );
+
+        Self {
+            tokens: Arc::new(tokens),
+            signing_key,
+            max_token_ttl: Duration::seconds(config.max_token_ttl_secs),
+        }
+    }
+
+    pub fn authenticate(&self, token: &str) -> Option<Principal> {
+        if token.starts_with(SIGNED_TOKEN_PREFIX) {
+            return self.verify_signed(token);
+        }
+
+        // check every token so timing doesn't reveal which one was close
+        let mut found = None;
+        for candidate in self.tokens.iter() {
+            let matches: bool = candidate.token.as_bytes().ct_eq(token.as_bytes()).into();
+            if matches {
+                found = Some(candidate.principal.clone());
+            }
+        }
+        found
+    }
+
+    /// Issues a short-lived token for `principal`, capped at the configured max ttl.
+    pub fn issue(
+        &self,
+        principal: Principal,
+        ttl: Duration,
+    ) -> Result<(String, DateTime<Utc>), anyhow::Error> {
+        let signing_key = self
+            .signing_key
+            .as_deref()
+            .ok_or_else(|| anyhow::anyhow!("ADMIN_SIGNING_KEY is not configured"))?;
+
+        let expires_at = Utc::now() + ttl.min(self.max_token_ttl);
+        let claims = SignedClaims {
+            principal,
+            exp: expires_at.timestamp(),
+        };
+        let payload = format!(
+            "{}{}",
+            SIGNED_TOKEN_PREFIX,
+            URL_SAFE_NO_PAD.encode(serde_json::to_vec(&claims)?)
+        );
+        let signature = URL_SAFE_NO_PAD.encode(sign(signing_key, &payload));
+
+        Ok((format!("{}.{}", payload, signature), expires_at))
+    }
+
+    fn verify_signed(&self, token: &str) -> Option<Principal> {
+        let signing_key = self.signing_key.as_deref()?;
+        let (payload, signature) = token.rsplit_once('.')?;
+        let signature = URL_SAFE_NO_PAD.decode(signature).ok()?;
+
+        let mut mac = Hmac::<Sha256>::new_from_slice(signing_key.as_bytes())
+            .expect("HMAC can take key of any size");
+        mac.update(payload.as_bytes());
+        if mac.verify_slice(&signature).is_err() {
+            warn!("Signed admin token has an invalid signature");
+            return None;
+        }
+
+        let claims = payload
+            .strip_prefix(SIGNED_TOKEN_PREFIX)
+            .and_then(|claims| URL_SAFE_NO_PAD.decode(claims).ok())
+            .and_then(|claims| serde_json::from_slice::<SignedClaims>(&claims).ok())?;
+
+        if claims.exp <= Utc::now().timestamp() {
+            warn!(
+                "Signed admin token for '{}' has expired",
+                claims.principal.name
+            );
+            return None;
+        }
+
+        Some(claims.principal)
+    }
+}
+
+fn sign(key: &str, payload: &str) -> Vec<u8> {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(key.as_bytes()).expect("HMAC can take key of any size");
+    mac.update(payload.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
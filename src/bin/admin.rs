@@ -0,0 +1,156 @@
+use std::env;
+
+use reqwest::{header::CONTENT_TYPE, Client, Method};
+
+const USAGE: &str = "usage: admin <command>
+
+commands:
+  webhooks [route] [limit]      list archived webhooks
+  webhook <id>                  show an archived webhook with headers and body
+  replay <id> [--dry-run]       run an archived webhook through the current handler
+  dead-letters                  list webhooks that ran out of retries
+  replay-dead-letter <id>       put a dead-lettered webhook back on the queue
+  schedules                     list scheduled jobs and their last run
+  runs [schedule]               show scheduled job run history
+  run <schedule> [--dry-run]    run a scheduled job now and stream its output
+  token <name> <ttl_secs> <scope>...
+                                issue a short-lived token with some of your scopes
+  posting <key>                 show a stored job posting and its revisions
+  save <key>                    save a job posting, to hear when it changes or closes
+  unsave <key>                  stop following a saved job posting
+  applications [status]         list saved job postings, or only the ones at a status
+  status <key> <status> [note]  move a saved job along: saved, applied, interviewing,
+                                rejected or offer
+
+env:
+  WEBHOOKS_URL    server base url (default http://localhost:8000)
+  ADMIN_TOKEN     bearer token for the admin api";
+
+#[tokio::main]
+async fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let base_url = env::var("WEBHOOKS_URL").unwrap_or_else(|_| "http://localhost:8000".to_string());
+    let token = env::var("ADMIN_TOKEN").unwrap_or_default();
+
+    let mut body = None;
+    let (method, path) = match args
+        .iter()
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .as_slice()
+    {
+        ["webhooks"] => (Method::GET, "/admin/webhooks".to_string()),
+        ["webhooks", route] => (Method::GET, format!("/admin/webhooks?route={}", route)),
+        ["webhooks", route, limit] => (
+            Method::GET,
+            format!("/admin/webhooks?route={}&limit={}", route, limit),
+        ),
+        ["webhook", id] => (Method::GET, format!("/admin/webhooks/{}", id)),
+        ["replay", id] => (Method::POST, format!("/admin/webhooks/{}/replay", id)),
+        ["replay", id, "--dry-run"] => (
+            Method::POST,
+            format!("/admin/webhooks/{}/replay?dry_run=true", id),
+        ),
+        ["dead-letters"] => (Method::GET, "/admin/dead-letters".to_string()),
+        ["replay-dead-letter", id] => (Method::POST, format!("/admin/dead-letters/{}/replay", id)),
+        ["schedules"] => (Method::GET, "/admin/schedules".to_string()),
+        ["runs"] => (Method::GET, "/admin/schedules/runs".to_string()),
+        ["runs", name] => (Method::GET, format!("/admin/schedules/{}/runs", name)),
+        ["run", name] => (Method::POST, format!("/admin/schedules/{}/run", name)),
+        ["run", name, "--dry-run"] => (
+            Method::POST,
+            format!("/admin/schedules/{}/run?dry_run=true", name),
+        ),
+        ["posting", key] => (Method::GET, format!("/admin/postings/{}", encode(key))),
+        ["save", key] => (Method::PUT, format!("/admin/postings/{}/saved", encode(key))),
+        ["unsave", key] => (
+            Method::DELETE,
+            format!("/admin/postings/{}/saved", encode(key)),
+        ),
+        ["applications"] => (Method::GET, "/admin/postings".to_string()),
+        ["applications", status] => (Method::GET, format!("/admin/postings?status={}", status)),
+        ["status", key, status, note @ ..] => {
+            body = Some(serde_json::json!({
+                "status": status,
+                "note": (!note.is_empty()).then(|| note.join(" ")),
+            }));
+            (
+                Method::PUT,
+                format!("/admin/postings/{}/status", encode(key)),
+            )
+        }
+        ["token", name, ttl_secs, scopes @ ..] if !scopes.is_empty() => {
+            let ttl_secs: i64 = ttl_secs.parse().unwrap_or_else(|_| {
+                eprintln!("ttl_secs must be a number\n\n{}", USAGE);
+                std::process::exit(2);
+            });
+            body = Some(serde_json::json!({
+                "name": name,
+                "ttl_secs": ttl_secs,
+                "scopes": scopes,
+            }));
+            (Method::POST, "/admin/tokens".to_string())
+        }
+        _ => {
+            eprintln!("{}", USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    let mut request = Client::new()
+        .request(
+            method,
+            format!("{}{}", base_url.trim_end_matches('/'), path),
+        )
+        .bearer_auth(token);
+    if let Some(body) = body {
+        request = request
+            .header(CONTENT_TYPE, "application/json")
+            .body(body.to_string());
+    }
+
+    let mut response = request
+        .send()
+        .await
+        .expect("Failed to reach the webhooks server");
+
+    let status = response.status();
+
+    // manual runs stream server-sent events, print them as they come in
+    let is_stream = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .is_some_and(|value| value.as_bytes().starts_with(b"text/event-stream"));
+    if is_stream {
+        while let Ok(Some(chunk)) = response.chunk().await {
+            print!("{}", String::from_utf8_lossy(&chunk));
+        }
+        return;
+    }
+
+    let body = response.text().await.unwrap_or_default();
+
+    // pretty print when the server answered with json, raw text otherwise
+    match serde_json::from_str::<serde_json::Value>(&body) {
+        Ok(json) => println!("{}", serde_json::to_string_pretty(&json).unwrap()),
+        Err(_) => println!("{}", body),
+    }
+
+    if !status.is_success() {
+        eprintln!("request failed with status {}", status);
+        std::process::exit(1);
+    }
+}
+
+/// Percent-encodes a path segment, posting keys have slashes and colons in them.
+fn encode(segment: &str) -> String {
+    segment
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
@@ -1,94 +1,306 @@
-use std::{collections::HashMap, fs};
+//! Runs the job alert parsers over every email in `fixtures/emails` and compares
+//! the jobs found with the `.json` file next to it.
+//!
+//! Each folder in `fixtures/workday` is a Workday site, `site.toml` plus the
+//! recorded responses as `offset-<n>.json`. They're served from a local server
+//! the adapter is pointed at, and the postings compared with `<folder>.json`.
+//! `fixtures/boards` works the same for Greenhouse and Lever, with `board.toml`
+//! and the one recorded `response.json`.
+//!
+//! Job descriptions in `fixtures/descriptions`, as `.html` or `.txt`, are run
+//! through the analysis with the built-in skill list.
+//!
+//! Replies to job digests in `fixtures/replies` are read as commands.
+//!
+//! `cargo run --bin test` to check, `cargo run --bin test -- --update` to rewrite
+//! the expected files after an intentional change.
 
-use mail_parser::MessageParser;
-use regex::Regex;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::ExitCode,
+    sync::Arc,
+};
 
-#[derive(PartialEq, Eq, Hash, Debug)]
-enum JobAlertSource {
-    Linkedin,
-    // Glassdoor,
+use axum::{
+    extract::{Path as UrlPath, State},
+    http::{header::CONTENT_TYPE, StatusCode},
+    response::IntoResponse,
+    routing::{get, post},
+    Json, Router,
+};
+
+#[allow(dead_code)]
+#[path = "../models/mod.rs"]
+mod models;
+
+// the alert parsers find these as `super::links`, same as under job_checker
+#[allow(dead_code)]
+#[path = "../helpers/job_checker/links.rs"]
+mod links;
+
+#[allow(dead_code)]
+#[path = "../helpers/job_checker/alerts/mod.rs"]
+mod alerts;
+
+#[allow(dead_code)]
+#[path = "../helpers/job_checker/workday.rs"]
+mod workday;
+
+#[allow(dead_code)]
+#[path = "../helpers/job_checker/boards.rs"]
+mod boards;
+
+#[path = "../helpers/job_checker/analysis.rs"]
+mod analysis;
+
+#[allow(dead_code)]
+#[path = "../helpers/job_checker/commands.rs"]
+mod commands;
+
+const FIXTURES_DIR: &str = "fixtures/emails";
+const WORKDAY_FIXTURES_DIR: &str = "fixtures/workday";
+const BOARD_FIXTURES_DIR: &str = "fixtures/boards";
+const DESCRIPTION_FIXTURES_DIR: &str = "fixtures/descriptions";
+const REPLY_FIXTURES_DIR: &str = "fixtures/replies";
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let update = std::env::args().any(|arg| arg == "--update");
+
+    let mut fixtures: Vec<_> = fs::read_dir(FIXTURES_DIR)
+        .expect("run from the repo root")
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "eml"))
+        .collect();
+    fixtures.sort();
+
+    let sites = fixture_dirs(WORKDAY_FIXTURES_DIR);
+    let boards = fixture_dirs(BOARD_FIXTURES_DIR);
+    let mut descriptions: Vec<_> = fs::read_dir(DESCRIPTION_FIXTURES_DIR)
+        .expect("run from the repo root")
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|ext| ext == "html" || ext == "txt")
+        })
+        .collect();
+    descriptions.sort();
+    let mut replies: Vec<_> = fs::read_dir(REPLY_FIXTURES_DIR)
+        .expect("run from the repo root")
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "eml"))
+        .collect();
+    replies.sort();
+
+    let mut failed = 0;
+    for fixture in &fixtures {
+        if let Err(e) = check(fixture, update) {
+            println!("FAIL {}: {}", fixture.display(), e);
+            failed += 1;
+        }
+    }
+    for site in &sites {
+        if let Err(e) = check_workday(site, update).await {
+            println!("FAIL {}: {}", site.display(), e);
+            failed += 1;
+        }
+    }
+    for board in &boards {
+        if let Err(e) = check_board(board, update).await {
+            println!("FAIL {}: {}", board.display(), e);
+            failed += 1;
+        }
+    }
+
+    for description in &descriptions {
+        if let Err(e) = check_description(description, update) {
+            println!("FAIL {}: {}", description.display(), e);
+            failed += 1;
+        }
+    }
+    for reply in &replies {
+        if let Err(e) = check_reply(reply, update) {
+            println!("FAIL {}: {}", reply.display(), e);
+            failed += 1;
+        }
+    }
+
+    let total = fixtures.len() + sites.len() + boards.len() + descriptions.len() + replies.len();
+    println!("{} fixtures, {} failed", total, failed);
+    if failed > 0 {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
 }
 
-#[derive(PartialEq, Eq, Hash, Debug)]
-struct ParsedJob {
-    source: JobAlertSource,
-    job_id: String,
-    title: String,
-    location: String,
-    link: String,
+fn fixture_dirs(dir: &str) -> Vec<PathBuf> {
+    let mut dirs: Vec<_> = fs::read_dir(dir)
+        .expect("run from the repo root")
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_dir())
+        .collect();
+    dirs.sort();
+    dirs
 }
 
+fn check(fixture: &Path, update: bool) -> Result<(), anyhow::Error> {
+    let raw = fs::read(fixture)?;
+    let email = alerts::AlertEmail::parse(&raw)?;
 
+    let mut jobs = alerts::parse_jobs(&email);
+    jobs.sort_by(|a, b| a.job_id.cmp(&b.job_id));
+    let found = serde_json::to_value(&jobs)?;
 
-fn main() {
-    let raw_email_contents = fs::read("test_mail.txt").expect("File should be in the same dir");
+    compare(fixture, &found, &format!("{} jobs", jobs.len()), update)
+}
 
-    let parsed_email = MessageParser::default().parse(&raw_email_contents).unwrap();
+/// Serves the recorded pages of a Workday site and runs the adapter against it.
+async fn check_workday(site: &Path, update: bool) -> Result<(), anyhow::Error> {
+    let mut config: workday::WorkdayConfig =
+        toml::from_str(&fs::read_to_string(site.join("site.toml"))?)?;
 
-    println!(
-        "email from : {:?}, to: {:?} \n subject: {:?}",
-        parsed_email.from().unwrap(),
-        parsed_email.to().unwrap(),
-        parsed_email.subject().unwrap()
-    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+    config.base_url = format!("http://{}", listener.local_addr()?);
+    let recorded = Arc::new(RecordedSite {
+        dir: site.to_path_buf(),
+        tenant: config.tenant.clone(),
+        site: config.site.clone(),
+    });
+    let app = Router::new()
+        .route("/wday/cxs/{tenant}/{site}/jobs", post(recorded_page))
+        .with_state(recorded);
+    let server = tokio::spawn(async move { axum::serve(listener, app).await });
+
+    let postings = workday::fetch(&reqwest::Client::new(), &config).await;
+    server.abort();
+
+    // the port changes every run
+    let found = serde_json::to_value(&postings?)?
+        .to_string()
+        .replace(&config.base_url, "http://workday.test")
+        .replace("url:127.0.0.1/", "url:workday.test/");
+    let found: serde_json::Value = serde_json::from_str(&found)?;
+    let count = found.as_array().map_or(0, |postings| postings.len());
+
+    compare(site, &found, &format!("{} jobs", count), update)
+}
 
-    println!("{:?}", parsed_email.body_text(0).unwrap());
-    
+struct RecordedSite {
+    dir: PathBuf,
+    tenant: String,
+    site: String,
+}
 
-    let mut jobs: HashMap<String, ParsedJob> = HashMap::new();
+/// Answers with `offset-<n>.json`, 404 for anything that wasn't recorded.
+async fn recorded_page(
+    State(recorded): State<Arc<RecordedSite>>,
+    UrlPath((tenant, site)): UrlPath<(String, String)>,
+    Json(request): Json<serde_json::Value>,
+) -> impl IntoResponse {
+    let offset = request["offset"].as_u64();
+    let limit = request["limit"].as_u64();
+    if tenant != recorded.tenant || site != recorded.site || offset.is_none() || limit.is_none() {
+        return (StatusCode::NOT_FOUND, "not recorded".to_string()).into_response();
+    }
 
-    if let Some(body) = parsed_email.body_text(0) {
-        let re = Regex::new(
-            r"(?<line_before>[^\n]+)\n<https://www\.linkedin\.com/comm/jobs/view/(?<job_id>\d{10})",
-        )
-        .unwrap();
+    match fs::read_to_string(recorded.dir.join(format!("offset-{}.json", offset.unwrap()))) {
+        Ok(body) => ([(CONTENT_TYPE, "application/json")], body).into_response(),
+        Err(_) => (StatusCode::NOT_FOUND, "not recorded".to_string()).into_response(),
+    }
+}
 
-        for caps in re.captures_iter(&body) {
-            let line_before_url = caps["line_before"].trim().to_string();
-            let job_id = caps["job_id"].to_string();
-            let job_link = format!("https://www.linkedin.com/comm/jobs/view/{}", &job_id);
+/// Serves a board's recorded response and runs the adapter and its filters against it.
+async fn check_board(board: &Path, update: bool) -> Result<(), anyhow::Error> {
+    let mut config: boards::JobBoardConfig =
+        toml::from_str(&fs::read_to_string(board.join("board.toml"))?)?;
 
-            if line_before_url.starts_with("[image:")
-                || line_before_url.starts_with("Actively recruiting")
-                || line_before_url.ends_with("connection")
-                || line_before_url.is_empty()
-            {
-                continue;
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+    config.api_url = Some(format!("http://{}", listener.local_addr()?));
+    let response = board.join("response.json");
+    // the paths each provider's api is at, anything else is a 404
+    let path = match config.provider {
+        boards::BoardProvider::Greenhouse => format!("/v1/boards/{}/jobs", config.slug),
+        boards::BoardProvider::Lever => format!("/v0/postings/{}", config.slug),
+    };
+    let app = Router::new().route(
+        &path,
+        get(|| async move {
+            match fs::read_to_string(response) {
+                Ok(body) => ([(CONTENT_TYPE, "application/json")], body).into_response(),
+                Err(_) => (StatusCode::NOT_FOUND, "not recorded".to_string()).into_response(),
             }
+        }),
+    );
+    let server = tokio::spawn(async move { axum::serve(listener, app).await });
 
-            // println!("Found LinkedIn job:");
-            // println!("  Line before URL: {}", line_before_url);
-            // println!("  Job ID: {}", job_id);
-            // println!(
-            //     "  Full URL: https://www.linkedin.com/comm/jobs/view/{}",
-            //     job_id
-            // );
-            // println!();
-
-            jobs.insert(
-                job_id.clone(),
-                ParsedJob {
-                    link: job_link,
-                    title: {
-                        if line_before_url.starts_with("*") && line_before_url.ends_with("*") {
-                            line_before_url.clone()
-                        } else {
-                            jobs.get(&job_id).unwrap().title.clone()
-                        }
-                    },
-                    location: {
-                        if !(line_before_url.starts_with("*") && line_before_url.ends_with("*")) {
-                            line_before_url
-                        } else {
-                            "".to_owned()
-                        }
-                    },
-                    source: JobAlertSource::Linkedin,
-                    job_id,
-                },
-            );
-        }
+    let postings = boards::fetch(&reqwest::Client::new(), &config).await;
+    server.abort();
+
+    let postings: Vec<_> = postings?
+        .into_iter()
+        .filter(|posting| config.wanted(posting))
+        .collect();
+    let found = serde_json::to_value(&postings)?;
+    compare(board, &found, &format!("{} jobs", postings.len()), update)
+}
+
+fn check_description(description: &Path, update: bool) -> Result<(), anyhow::Error> {
+    let text = fs::read_to_string(description)?;
+    let analysis = analysis::analyze(&text, &analysis::AnalysisConfig::default());
+    let found = serde_json::to_value(&analysis)?;
+
+    compare(
+        description,
+        &found,
+        &format!("{} skills", analysis.skills.len()),
+        update,
+    )
+}
+
+fn check_reply(reply: &Path, update: bool) -> Result<(), anyhow::Error> {
+    let raw = fs::read(reply)?;
+    let parsed = commands::parse(&raw)?;
+    let found = serde_json::to_value(&parsed)?;
+
+    compare(
+        reply,
+        &found,
+        &format!("{} commands", parsed.commands.len()),
+        update,
+    )
+}
+
+fn compare(
+    fixture: &Path,
+    found: &serde_json::Value,
+    summary: &str,
+    update: bool,
+) -> Result<(), anyhow::Error> {
+    let expected_path = fixture.with_extension("json");
+    if update {
+        fs::write(&expected_path, serde_json::to_string_pretty(found)? + "\n")?;
+        println!("UPDATED {} ({})", expected_path.display(), summary);
+        return Ok(());
+    }
+
+    let expected: serde_json::Value = match fs::read_to_string(&expected_path) {
+        Ok(expected) => serde_json::from_str(&expected)?,
+        Err(_) => anyhow::bail!(
+            "no {}, run with --update to create it",
+            expected_path.display()
+        ),
+    };
+
+    if *found != expected {
+        anyhow::bail!(
+            "results don't match {}\nfound:\n{}",
+            expected_path.display(),
+            serde_json::to_string_pretty(found)?
+        );
     }
 
-    println!("jobs found: {:?}", &jobs);
+    println!("PASS {} ({})", fixture.display(), summary);
+    Ok(())
 }
@@ -0,0 +1,602 @@
+use std::{fs, net::SocketAddr, path::Path, str::FromStr};
+
+use anyhow::{anyhow, Context};
+use apalis_cron::Schedule;
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::{
+    auth::AuthConfig,
+    helpers::job_checker::{
+        analysis::AnalysisConfig, boards::JobBoardConfig, workday::WorkdayConfig,
+    },
+};
+
+pub const DEFAULT_CONFIG_PATH: &str = "config.toml";
+
+/// Non-secret settings loaded from `config.toml`. Credentials stay in the shuttle secrets.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AppConfig {
+    #[serde(default)]
+    pub schedules: Vec<ScheduleConfig>,
+    #[serde(default)]
+    pub auth: AuthConfig,
+    /// Built-in mail receiver, off unless the section is present
+    pub smtp: Option<SmtpConfig>,
+    /// IMAP folders polled by the `imap_poll` task
+    #[serde(default)]
+    pub mailboxes: Vec<MailboxConfig>,
+    /// How jobs from different sources are matched up
+    #[serde(default)]
+    pub matching: MatchingConfig,
+    /// Workday career sites checked by the `workday` task
+    #[serde(default)]
+    pub workday: Vec<WorkdayConfig>,
+    /// Greenhouse and Lever boards checked by the `job_boards` task
+    #[serde(default)]
+    pub job_boards: Vec<JobBoardConfig>,
+    /// Search run by the `optum` task
+    #[serde(default)]
+    pub optum: OptumConfig,
+    #[serde(default)]
+    pub tracking: TrackingConfig,
+    /// What's pulled out of job descriptions
+    #[serde(default)]
+    pub analysis: AnalysisConfig,
+    /// Which jobs are worth an email, going by their descriptions
+    #[serde(default)]
+    pub filters: FilterConfig,
+    /// Keeping up with applications to saved jobs
+    #[serde(default)]
+    pub applications: ApplicationsConfig,
+    /// Commands sent by replying to job digests, off unless the section is present
+    pub replies: Option<RepliesConfig>,
+}
+
+impl AppConfig {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, anyhow::Error> {
+        let path = path.as_ref();
+        info!("Loading config from {}", path.display());
+
+        let raw = fs::read_to_string(path)
+            .with_context(|| format!("failed to read config file {}", path.display()))?;
+        let config: AppConfig = toml::from_str(&raw)
+            .with_context(|| format!("failed to parse config file {}", path.display()))?;
+        config.validate()?;
+
+        info!("Loaded {} schedule(s) from config", config.schedules.len());
+        Ok(config)
+    }
+
+    fn validate(&self) -> Result<(), anyhow::Error> {
+        for (i, schedule) in self.schedules.iter().enumerate() {
+            if self.schedules[..i].iter().any(|s| s.name == schedule.name) {
+                return Err(anyhow!("duplicate schedule name '{}'", schedule.name));
+            }
+            schedule.cron_schedule()?;
+            schedule.timezone()?;
+        }
+        if let Some(smtp) = &self.smtp {
+            smtp.validate()?;
+        }
+        for (i, mailbox) in self.mailboxes.iter().enumerate() {
+            if self.mailboxes[..i].iter().any(|m| m.name == mailbox.name) {
+                return Err(anyhow!("duplicate mailbox name '{}'", mailbox.name));
+            }
+        }
+        self.matching.validate()?;
+        self.filters.validate()?;
+        for skill in &self.filters.skills {
+            if !self.analysis.skills.contains_key(skill) {
+                return Err(anyhow!(
+                    "filters skill '{}' isn't in analysis.skills",
+                    skill
+                ));
+            }
+        }
+        self.optum.validate()?;
+        for (i, site) in self.workday.iter().enumerate() {
+            if self.workday[..i].iter().any(|s| s.name == site.name) {
+                return Err(anyhow!("duplicate workday site name '{}'", site.name));
+            }
+            site.validate()?;
+        }
+        for (i, board) in self.job_boards.iter().enumerate() {
+            if self.job_boards[..i].iter().any(|b| b.name == board.name) {
+                return Err(anyhow!("duplicate job board name '{}'", board.name));
+            }
+            board.validate()?;
+        }
+        if let Some(replies) = &self.replies {
+            replies.validate()?;
+        }
+        Ok(())
+    }
+
+    pub fn schedule(&self, name: &str) -> Option<&ScheduleConfig> {
+        self.schedules.iter().find(|s| s.name == name)
+    }
+}
+
+/// What a scheduled job actually runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScheduledTask {
+    /// Check the Optum careers api for student internships
+    Optum,
+    /// Make sure the database is reachable and nothing is piling up in the dead letters
+    HealthCheck,
+    /// Fetch new job alert emails from every configured mailbox
+    ImapPoll,
+    /// Check every configured Workday career site
+    Workday,
+    /// Check every configured Greenhouse and Lever board
+    JobBoards,
+    /// Email about saved jobs that are about to close and applications that went quiet
+    JobReminders,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduleConfig {
+    pub name: String,
+    pub task: ScheduledTask,
+    /// Format: sec min hour day_of_month month day_of_week (year)
+    pub cron: String,
+    #[serde(default = "default_timezone")]
+    pub timezone: String,
+    #[serde(default)]
+    pub retries: usize,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// What to do about ticks missed while the server was down
+    #[serde(default)]
+    pub catch_up: CatchUpPolicy,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CatchUpPolicy {
+    #[default]
+    Skip,
+    RunOnce,
+    /// Only the latest 50 missed ticks, see `scheduler::MAX_CATCH_UP_RUNS`
+    RunAll,
+}
+
+impl ScheduleConfig {
+    pub fn cron_schedule(&self) -> Result<Schedule, anyhow::Error> {
+        Schedule::from_str(&self.cron).map_err(|e| {
+            anyhow!(
+                "invalid cron expression '{}' for schedule '{}': {}",
+                self.cron,
+                self.name,
+                e
+            )
+        })
+    }
+
+    pub fn timezone(&self) -> Result<Tz, anyhow::Error> {
+        self.timezone.parse::<Tz>().map_err(|e| {
+            anyhow!(
+                "invalid timezone '{}' for schedule '{}': {}",
+                self.timezone,
+                self.name,
+                e
+            )
+        })
+    }
+}
+
+fn default_timezone() -> String {
+    "UTC".to_string()
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MailProtocol {
+    /// Plain SMTP, for pointing an MX record straight at the server
+    #[default]
+    Smtp,
+    /// LMTP, for delivery from a local MTA like postfix
+    Lmtp,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SmtpConfig {
+    pub bind: String,
+    #[serde(default)]
+    pub protocol: MailProtocol,
+    /// Name used in the greeting and HELO/LHLO replies
+    #[serde(default = "default_smtp_hostname")]
+    pub hostname: String,
+    /// Addresses mail is accepted for, everything else is refused at RCPT TO
+    pub recipients: Vec<String>,
+    #[serde(default = "default_max_message_bytes")]
+    pub max_message_bytes: usize,
+}
+
+impl SmtpConfig {
+    pub fn bind_addr(&self) -> Result<SocketAddr, anyhow::Error> {
+        self.bind
+            .parse()
+            .map_err(|e| anyhow!("invalid smtp bind address '{}': {}", self.bind, e))
+    }
+
+    pub fn accepts(&self, address: &str) -> bool {
+        self.recipients
+            .iter()
+            .any(|recipient| recipient.eq_ignore_ascii_case(address))
+    }
+
+    fn validate(&self) -> Result<(), anyhow::Error> {
+        self.bind_addr()?;
+        if self.recipients.is_empty() {
+            return Err(anyhow!("smtp listener needs at least one recipient"));
+        }
+        Ok(())
+    }
+}
+
+fn default_smtp_hostname() -> String {
+    "rust-webhooks".to_string()
+}
+
+fn default_max_message_bytes() -> usize {
+    25 * 1024 * 1024
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct MailboxConfig {
+    pub name: String,
+    pub host: String,
+    #[serde(default = "default_imap_port")]
+    pub port: u16,
+    /// Only turn off for a local IMAP server
+    #[serde(default = "default_enabled")]
+    pub tls: bool,
+    pub username: String,
+    /// Name of the secret holding the password (an app password for gmail)
+    pub password_secret: String,
+    #[serde(default = "default_imap_folder")]
+    pub folder: String,
+    /// IMAP SEARCH criteria, e.g. `UNSEEN FROM "jobalerts-noreply@linkedin.com"`
+    #[serde(default = "default_imap_search")]
+    pub search: String,
+    /// Folder to move handled messages to, they're only marked as seen otherwise
+    pub move_to: Option<String>,
+    /// Cap per poll, so a big backlog is worked through over several runs
+    #[serde(default = "default_imap_max_messages")]
+    pub max_messages: usize,
+}
+
+fn default_imap_port() -> u16 {
+    993
+}
+
+fn default_imap_folder() -> String {
+    "INBOX".to_string()
+}
+
+fn default_imap_search() -> String {
+    "UNSEEN".to_string()
+}
+
+fn default_imap_max_messages() -> usize {
+    50
+}
+
+/// Similarity thresholds, from 0 (anything goes) to 1 (identical after
+/// normalizing), for treating postings from different sources as one job.
+#[derive(Debug, Clone, Deserialize)]
+pub struct MatchingConfig {
+    /// Compared without suffixes like Inc. and Ltd.
+    #[serde(default = "default_company_threshold")]
+    pub company_threshold: f64,
+    #[serde(default = "default_title_threshold")]
+    pub title_threshold: f64,
+    /// Compared by city, skipped when either posting is remote or has no location
+    #[serde(default = "default_location_threshold")]
+    pub location_threshold: f64,
+    /// How far back stored postings are matched against new ones
+    #[serde(default = "default_lookback_days")]
+    pub lookback_days: u32,
+}
+
+impl Default for MatchingConfig {
+    fn default() -> Self {
+        Self {
+            company_threshold: default_company_threshold(),
+            title_threshold: default_title_threshold(),
+            location_threshold: default_location_threshold(),
+            lookback_days: default_lookback_days(),
+        }
+    }
+}
+
+impl MatchingConfig {
+    fn validate(&self) -> Result<(), anyhow::Error> {
+        for (name, threshold) in [
+            ("company_threshold", self.company_threshold),
+            ("title_threshold", self.title_threshold),
+            ("location_threshold", self.location_threshold),
+        ] {
+            if !(0.0..=1.0).contains(&threshold) {
+                return Err(anyhow!(
+                    "matching {} must be between 0 and 1, got {}",
+                    name,
+                    threshold
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn default_company_threshold() -> f64 {
+    0.85
+}
+
+fn default_title_threshold() -> f64 {
+    0.8
+}
+
+fn default_location_threshold() -> f64 {
+    0.7
+}
+
+fn default_lookback_days() -> u32 {
+    60
+}
+
+/// Facets for the Optum (m-cloud) jobs api, the same ones the careers site
+/// puts in its own searches.
+#[derive(Debug, Clone, Deserialize)]
+pub struct OptumConfig {
+    #[serde(default = "default_optum_organization")]
+    pub organization: u32,
+    /// `parent_category` facet, e.g. `Optum`
+    #[serde(default = "default_optum_categories")]
+    pub categories: Vec<String>,
+    /// `level` facet, e.g. `Student Internships`
+    #[serde(default = "default_optum_levels")]
+    pub levels: Vec<String>,
+    /// Only jobs near here, anywhere when left out
+    pub near: Option<OptumLocation>,
+    #[serde(default = "default_optum_page_size")]
+    pub page_size: u32,
+    #[serde(default = "default_optum_max_pages")]
+    pub max_pages: u32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct OptumLocation {
+    pub latitude: f64,
+    pub longitude: f64,
+    /// In miles
+    #[serde(default = "default_optum_radius")]
+    pub radius: u32,
+}
+
+impl Default for OptumConfig {
+    fn default() -> Self {
+        Self {
+            organization: default_optum_organization(),
+            categories: default_optum_categories(),
+            levels: default_optum_levels(),
+            near: None,
+            page_size: default_optum_page_size(),
+            max_pages: default_optum_max_pages(),
+        }
+    }
+}
+
+impl OptumConfig {
+    fn validate(&self) -> Result<(), anyhow::Error> {
+        if self.page_size == 0 || self.max_pages == 0 {
+            return Err(anyhow!("optum page_size and max_pages must be at least 1"));
+        }
+        if let Some(near) = &self.near {
+            if !(-90.0..=90.0).contains(&near.latitude)
+                || !(-180.0..=180.0).contains(&near.longitude)
+            {
+                return Err(anyhow!(
+                    "invalid optum location {}, {}",
+                    near.latitude,
+                    near.longitude
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn default_optum_organization() -> u32 {
+    2071
+}
+
+fn default_optum_categories() -> Vec<String> {
+    vec!["Optum".to_string()]
+}
+
+fn default_optum_levels() -> Vec<String> {
+    vec!["Student Internships".to_string()]
+}
+
+fn default_optum_page_size() -> u32 {
+    50
+}
+
+fn default_optum_max_pages() -> u32 {
+    10
+}
+
+fn default_optum_radius() -> u32 {
+    50
+}
+
+/// Keeping up with postings after they're first seen.
+#[derive(Debug, Clone, Deserialize)]
+pub struct TrackingConfig {
+    /// How many days before a saved job's deadline to send a reminder
+    #[serde(default = "default_closing_soon_days")]
+    pub closing_soon_days: u32,
+}
+
+impl Default for TrackingConfig {
+    fn default() -> Self {
+        Self {
+            closing_soon_days: default_closing_soon_days(),
+        }
+    }
+}
+
+fn default_closing_soon_days() -> u32 {
+    3
+}
+
+/// Jobs are only filtered on what their description says, one that doesn't
+/// mention pay is kept whatever `min_yearly_pay` is.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct FilterConfig {
+    /// Skip jobs asking for more years of experience than this
+    pub max_years_experience: Option<u32>,
+    /// Skip jobs paying less than this a year at most, hourly pay counts as
+    /// 2080 hours. Currencies aren't converted.
+    pub min_yearly_pay: Option<f64>,
+    /// Skip terms longer than this many months
+    pub max_term_months: Option<u32>,
+    /// Jobs listing any skills need one of these, names from `[analysis.skills]`
+    #[serde(default)]
+    pub skills: Vec<String>,
+}
+
+impl FilterConfig {
+    fn validate(&self) -> Result<(), anyhow::Error> {
+        if self.min_yearly_pay.is_some_and(|pay| pay < 0.0) {
+            return Err(anyhow!("filters min_yearly_pay can't be negative"));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ApplicationsConfig {
+    /// How many days an application can sit at applied or interviewing before
+    /// the `job_reminders` task sends a nudge
+    #[serde(default = "default_stale_after_days")]
+    pub stale_after_days: u32,
+    /// Mirror statuses to the job alert Notion database, off unless the section is present
+    pub notion: Option<NotionSyncConfig>,
+}
+
+impl Default for ApplicationsConfig {
+    fn default() -> Self {
+        Self {
+            stale_after_days: default_stale_after_days(),
+            notion: None,
+        }
+    }
+}
+
+fn default_stale_after_days() -> u32 {
+    14
+}
+
+/// Names of the Notion database's properties, the optional ones are left
+/// alone when not set.
+#[derive(Debug, Clone, Deserialize)]
+pub struct NotionSyncConfig {
+    /// A title property, set to the job title
+    #[serde(default = "default_notion_title_property")]
+    pub title_property: String,
+    /// A select property, set to `Saved`, `Applied`, `Interviewing`, `Rejected` or `Offer`
+    #[serde(default = "default_notion_status_property")]
+    pub status_property: String,
+    /// A text property
+    pub company_property: Option<String>,
+    /// A url property
+    pub link_property: Option<String>,
+    /// A date property, set to when the status last changed
+    pub date_property: Option<String>,
+}
+
+fn default_notion_title_property() -> String {
+    "Name".to_string()
+}
+
+fn default_notion_status_property() -> String {
+    "Status".to_string()
+}
+
+/// Digests are sent with a Reply-To of `address` plus a token only that digest
+/// knows, e.g. `jobs-reply+<token>@dvnl.work`. Mail the Cloudflare worker
+/// forwards for it is read as commands instead of a job alert.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RepliesConfig {
+    pub address: String,
+    /// Who can send commands, replies from anyone else are dropped
+    pub senders: Vec<String>,
+}
+
+impl RepliesConfig {
+    pub fn reply_to(&self, token: &str) -> String {
+        match self.address.split_once('@') {
+            Some((local, domain)) => format!("{}+{}@{}", local, token, domain),
+            None => self.address.clone(),
+        }
+    }
+
+    /// Whether mail to `to` is for the replies address, with or without a token.
+    pub fn is_for(&self, to: &str) -> bool {
+        match (address_parts(to.trim()), address_parts(&self.address)) {
+            (Some((local, _, domain)), Some((own_local, _, own_domain))) => {
+                local.eq_ignore_ascii_case(own_local) && domain.eq_ignore_ascii_case(own_domain)
+            }
+            _ => false,
+        }
+    }
+
+    /// The token in an address replies were sent to.
+    pub fn token<'b>(&self, to: &'b str) -> Option<&'b str> {
+        if !self.is_for(to) {
+            return None;
+        }
+        address_parts(to.trim()).and_then(|(_, token, _)| token)
+    }
+
+    pub fn allows(&self, sender: &str) -> bool {
+        self.senders
+            .iter()
+            .any(|allowed| allowed.eq_ignore_ascii_case(sender))
+    }
+
+    fn validate(&self) -> Result<(), anyhow::Error> {
+        if !self.address.contains('@') {
+            return Err(anyhow!("invalid replies address '{}'", self.address));
+        }
+        if self.address.contains('+') {
+            return Err(anyhow!(
+                "replies address '{}' can't have a +tag, each digest adds its own",
+                self.address
+            ));
+        }
+        if self.senders.is_empty() {
+            return Err(anyhow!("replies needs at least one sender"));
+        }
+        Ok(())
+    }
+}
+
+/// `name+tag@domain` as the name, the tag if there is one, and the domain.
+fn address_parts(address: &str) -> Option<(&str, Option<&str>, &str)> {
+    let (local, domain) = address.split_once('@')?;
+    Some(match local.split_once('+') {
+        Some((name, tag)) => (name, Some(tag), domain),
+        None => (local, None, domain),
+    })
+}
@@ -0,0 +1,125 @@
+use std::fmt;
+
+use axum::{
+    extract::rejection::JsonRejection,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde_json::json;
+use tracing::error;
+
+use crate::helpers::error_reporter::{ErrorContext, ErrorReporter};
+
+/// Error returned by every handler. 4xx variants mean the caller sent
+/// something we'll never accept, 5xx variants are worth retrying.
+#[derive(Debug)]
+pub enum AppError {
+    BadRequest(String),
+    Unauthorized(String),
+    Forbidden(String),
+    NotFound(String),
+    UnprocessableEntity(String),
+    /// An external service (Resend, Notion, job boards) failed
+    Upstream(String),
+    Internal(anyhow::Error),
+}
+
+impl AppError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            AppError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            AppError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            AppError::Forbidden(_) => StatusCode::FORBIDDEN,
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            AppError::UnprocessableEntity(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            AppError::Upstream(_) => StatusCode::BAD_GATEWAY,
+            AppError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn code(&self) -> &'static str {
+        match self {
+            AppError::BadRequest(_) => "bad_request",
+            AppError::Unauthorized(_) => "unauthorized",
+            AppError::Forbidden(_) => "forbidden",
+            AppError::NotFound(_) => "not_found",
+            AppError::UnprocessableEntity(_) => "unprocessable_entity",
+            AppError::Upstream(_) => "upstream_error",
+            AppError::Internal(_) => "internal_error",
+        }
+    }
+
+    /// Sends server-side failures to the error reporter in the background, so
+    /// a slow alert doesn't hold up the response; client errors are only the
+    /// caller's problem so they're passed through untouched.
+    pub fn reported(self, reporter: &ErrorReporter, context: ErrorContext) -> Self {
+        if self.status().is_server_error() {
+            reporter.report_in_background(context, &self);
+        }
+        self
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::BadRequest(message)
+            | AppError::Unauthorized(message)
+            | AppError::Forbidden(message)
+            | AppError::NotFound(message)
+            | AppError::UnprocessableEntity(message)
+            | AppError::Upstream(message) => write!(f, "{}", message),
+            AppError::Internal(e) => write!(f, "{:#}", e),
+        }
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        let status = self.status();
+        let message = match &self {
+            // don't leak internals to callers, the reporter has the full error
+            AppError::Internal(e) => {
+                error!("Internal error: {:#}", e);
+                "Internal server error".to_string()
+            }
+            _ => self.to_string(),
+        };
+
+        (
+            status,
+            Json(json!({
+                "error": {
+                    "code": self.code(),
+                    "message": message,
+                }
+            })),
+        )
+            .into_response()
+    }
+}
+
+impl From<anyhow::Error> for AppError {
+    fn from(e: anyhow::Error) -> Self {
+        AppError::Internal(e)
+    }
+}
+
+impl From<JsonRejection> for AppError {
+    fn from(rejection: JsonRejection) -> Self {
+        if rejection.status() == StatusCode::UNPROCESSABLE_ENTITY {
+            AppError::UnprocessableEntity(rejection.body_text())
+        } else {
+            AppError::BadRequest(rejection.body_text())
+        }
+    }
+}
+
+impl From<resend_rs::Error> for AppError {
+    fn from(e: resend_rs::Error) -> Self {
+        AppError::Upstream(format!("Resend request failed: {}", e))
+    }
+}
@@ -0,0 +1,75 @@
+use axum::http::{header, HeaderMap};
+use chrono::Utc;
+use serde_json::{Map, Value};
+use sqlx::SqlitePool;
+use tracing::info;
+
+use crate::models::archive::{ArchivedWebhook, ArchivedWebhookSummary};
+
+// never persist credentials alongside the payload
+const REDACTED_HEADERS: [header::HeaderName; 2] = [header::AUTHORIZATION, header::COOKIE];
+
+/// Stores the raw body and headers of an inbound webhook, returning its id.
+pub async fn archive_webhook(
+    pool: &SqlitePool,
+    route: &str,
+    headers: &HeaderMap,
+    body: &[u8],
+) -> Result<String, anyhow::Error> {
+    let id = uuid::Uuid::new_v4().to_string();
+
+    let headers: Map<String, Value> = headers
+        .iter()
+        .filter(|(name, _)| !REDACTED_HEADERS.contains(name))
+        .map(|(name, value)| {
+            (
+                name.to_string(),
+                Value::String(String::from_utf8_lossy(value.as_bytes()).into_owned()),
+            )
+        })
+        .collect();
+
+    sqlx::query(
+        "INSERT INTO webhook_archive (id, route, headers, body, received_at) VALUES (?, ?, ?, ?, ?)",
+    )
+    .bind(&id)
+    .bind(route)
+    .bind(Value::Object(headers).to_string())
+    .bind(String::from_utf8_lossy(body).into_owned())
+    .bind(Utc::now().to_rfc3339())
+    .execute(pool)
+    .await?;
+
+    info!("Archived webhook {} for {} ({} bytes)", id, route, body.len());
+    Ok(id)
+}
+
+pub async fn list_webhooks(
+    pool: &SqlitePool,
+    route: Option<&str>,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<ArchivedWebhookSummary>, anyhow::Error> {
+    Ok(sqlx::query_as::<_, ArchivedWebhookSummary>(
+        "SELECT id, route, length(body) AS size, received_at FROM webhook_archive
+         WHERE ?1 IS NULL OR route = ?1
+         ORDER BY received_at DESC LIMIT ?2 OFFSET ?3",
+    )
+    .bind(route)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(pool)
+    .await?)
+}
+
+pub async fn get_webhook(
+    pool: &SqlitePool,
+    id: &str,
+) -> Result<Option<ArchivedWebhook>, anyhow::Error> {
+    Ok(
+        sqlx::query_as::<_, ArchivedWebhook>("SELECT * FROM webhook_archive WHERE id = ?")
+            .bind(id)
+            .fetch_optional(pool)
+            .await?,
+    )
+}
@@ -0,0 +1,97 @@
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+
+use crate::models::schedule::CronRun;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RunTrigger {
+    Cron,
+    CatchUp,
+    Manual,
+}
+
+impl RunTrigger {
+    fn as_str(&self) -> &'static str {
+        match self {
+            RunTrigger::Cron => "cron",
+            RunTrigger::CatchUp => "catch_up",
+            RunTrigger::Manual => "manual",
+        }
+    }
+}
+
+// fixed format so timestamps sort correctly as text
+fn timestamp(time: DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+pub async fn start_run(
+    pool: &SqlitePool,
+    schedule: &str,
+    scheduled_for: DateTime<Utc>,
+    trigger: RunTrigger,
+) -> Result<i64, anyhow::Error> {
+    let result = sqlx::query(
+        "INSERT INTO cron_runs (schedule, scheduled_for, started_at, outcome, trigger)
+         VALUES (?, ?, ?, 'running', ?)",
+    )
+    .bind(schedule)
+    .bind(timestamp(scheduled_for))
+    .bind(timestamp(Utc::now()))
+    .bind(trigger.as_str())
+    .execute(pool)
+    .await?;
+
+    Ok(result.last_insert_rowid())
+}
+
+pub async fn finish_run(
+    pool: &SqlitePool,
+    run_id: i64,
+    error: Option<&str>,
+) -> Result<(), anyhow::Error> {
+    sqlx::query("UPDATE cron_runs SET finished_at = ?, outcome = ?, error = ? WHERE id = ?")
+        .bind(timestamp(Utc::now()))
+        .bind(if error.is_some() { "failed" } else { "succeeded" })
+        .bind(error)
+        .bind(run_id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Most recent tick a schedule ran for, from cron or catch-up. Manual runs
+/// don't count since they aren't tied to the schedule.
+pub async fn last_scheduled_run(
+    pool: &SqlitePool,
+    schedule: &str,
+) -> Result<Option<DateTime<Utc>>, anyhow::Error> {
+    let last: Option<String> = sqlx::query_scalar(
+        "SELECT MAX(scheduled_for) FROM cron_runs WHERE schedule = ? AND trigger != 'manual'",
+    )
+    .bind(schedule)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(last
+        .map(|last| DateTime::parse_from_rfc3339(&last))
+        .transpose()?
+        .map(|last| last.with_timezone(&Utc)))
+}
+
+pub async fn list_runs(
+    pool: &SqlitePool,
+    schedule: Option<&str>,
+    limit: i64,
+) -> Result<Vec<CronRun>, anyhow::Error> {
+    Ok(sqlx::query_as::<_, CronRun>(
+        "SELECT * FROM cron_runs WHERE ?1 IS NULL OR schedule = ?1
+         ORDER BY started_at DESC, id DESC LIMIT ?2",
+    )
+    .bind(schedule)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?)
+}
@@ -0,0 +1,195 @@
+use std::path::Path;
+
+use apalis_sql::sqlite::SqliteStorage;
+use sqlx::{sqlite::SqlitePoolOptions, SqlitePool};
+use tracing::info;
+
+pub const DEFAULT_DATABASE_URL: &str = "sqlite://data/webhooks.db?mode=rwc";
+
+// tables owned by this app, apalis manages its own through `SqliteStorage::setup`
+const SCHEMA: &str = r#"
+CREATE TABLE IF NOT EXISTS dead_letters (
+    id TEXT PRIMARY KEY,
+    kind TEXT NOT NULL,
+    payload TEXT NOT NULL,
+    error TEXT NOT NULL,
+    attempts INTEGER NOT NULL,
+    failed_at TEXT NOT NULL,
+    replayed_at TEXT
+);
+
+CREATE TABLE IF NOT EXISTS webhook_archive (
+    id TEXT PRIMARY KEY,
+    route TEXT NOT NULL,
+    headers TEXT NOT NULL,
+    body TEXT NOT NULL,
+    received_at TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS webhook_archive_received_at ON webhook_archive (received_at);
+
+CREATE TABLE IF NOT EXISTS cron_runs (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    schedule TEXT NOT NULL,
+    scheduled_for TEXT NOT NULL,
+    started_at TEXT NOT NULL,
+    finished_at TEXT,
+    outcome TEXT NOT NULL,
+    error TEXT,
+    trigger TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS cron_runs_schedule ON cron_runs (schedule, scheduled_for);
+
+CREATE TABLE IF NOT EXISTS processed_emails (
+    message_id TEXT PRIMARY KEY,
+    payload_id TEXT NOT NULL,
+    processed_at TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS job_postings (
+    key TEXT PRIMARY KEY,
+    source TEXT NOT NULL,
+    title TEXT NOT NULL,
+    company TEXT,
+    location TEXT NOT NULL,
+    salary TEXT,
+    link TEXT NOT NULL,
+    first_seen_at TEXT NOT NULL,
+    last_seen_at TEXT NOT NULL,
+    description TEXT,
+    deadline TEXT,
+    feed TEXT,
+    closed_at TEXT,
+    saved_at TEXT,
+    closing_notified_at TEXT,
+    pay_min REAL,
+    pay_max REAL,
+    pay_currency TEXT,
+    pay_period TEXT,
+    term_months INTEGER,
+    term_start TEXT,
+    years_experience INTEGER,
+    skills TEXT,
+    status TEXT,
+    status_changed_at TEXT,
+    stale_notified_at TEXT,
+    notion_page_id TEXT
+);
+CREATE INDEX IF NOT EXISTS job_postings_last_seen_at ON job_postings (last_seen_at);
+
+CREATE TABLE IF NOT EXISTS job_revisions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    key TEXT NOT NULL,
+    field TEXT NOT NULL,
+    old_value TEXT,
+    new_value TEXT,
+    changed_at TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS job_revisions_key ON job_revisions (key, changed_at);
+
+CREATE TABLE IF NOT EXISTS application_events (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    key TEXT NOT NULL,
+    status TEXT NOT NULL,
+    note TEXT,
+    date TEXT NOT NULL,
+    created_at TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS application_events_key ON application_events (key);
+
+CREATE TABLE IF NOT EXISTS job_digests (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    subject TEXT NOT NULL,
+    sent_at TEXT NOT NULL,
+    token TEXT
+);
+
+CREATE TABLE IF NOT EXISTS digest_jobs (
+    digest_id INTEGER NOT NULL,
+    code INTEGER NOT NULL,
+    key TEXT NOT NULL,
+    PRIMARY KEY (digest_id, code)
+);
+
+CREATE TABLE IF NOT EXISTS filter_rules (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    kind TEXT NOT NULL,
+    value TEXT NOT NULL COLLATE NOCASE,
+    created_at TEXT NOT NULL,
+    UNIQUE (kind, value)
+);
+"#;
+
+// columns added to tables after they were first created, `CREATE TABLE IF NOT
+// EXISTS` leaves existing tables alone
+const ADDED_COLUMNS: &[(&str, &str, &str)] = &[
+    ("job_postings", "description", "TEXT"),
+    ("job_postings", "deadline", "TEXT"),
+    ("job_postings", "feed", "TEXT"),
+    ("job_postings", "closed_at", "TEXT"),
+    ("job_postings", "saved_at", "TEXT"),
+    ("job_postings", "closing_notified_at", "TEXT"),
+    ("job_postings", "pay_min", "REAL"),
+    ("job_postings", "pay_max", "REAL"),
+    ("job_postings", "pay_currency", "TEXT"),
+    ("job_postings", "pay_period", "TEXT"),
+    ("job_postings", "term_months", "INTEGER"),
+    ("job_postings", "term_start", "TEXT"),
+    ("job_postings", "years_experience", "INTEGER"),
+    ("job_postings", "skills", "TEXT"),
+    ("job_postings", "status", "TEXT"),
+    ("job_postings", "status_changed_at", "TEXT"),
+    ("job_postings", "stale_notified_at", "TEXT"),
+    ("job_postings", "notion_page_id", "TEXT"),
+    ("job_digests", "token", "TEXT"),
+];
+
+pub async fn init_pool(database_url: &str) -> Result<SqlitePool, anyhow::Error> {
+    info!("Connecting to database: {}", database_url);
+
+    // sqlite won't create missing parent directories for us
+    if let Some(path) = database_url
+        .strip_prefix("sqlite://")
+        .map(|rest| rest.split('?').next().unwrap_or(rest))
+    {
+        if let Some(parent) = Path::new(path).parent() {
+            if !parent.as_os_str().is_empty() {
+                std::fs::create_dir_all(parent)?;
+            }
+        }
+    }
+
+    let pool = SqlitePoolOptions::new()
+        .max_connections(5)
+        .connect(database_url)
+        .await?;
+
+    info!("Running database migrations");
+    SqliteStorage::setup(&pool).await?;
+    sqlx::raw_sql(SCHEMA).execute(&pool).await?;
+    add_missing_columns(&pool).await?;
+
+    info!("Database ready");
+    Ok(pool)
+}
+
+async fn add_missing_columns(pool: &SqlitePool) -> Result<(), anyhow::Error> {
+    for (table, column, column_type) in ADDED_COLUMNS {
+        let exists: bool =
+            sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM pragma_table_info(?) WHERE name = ?)")
+                .bind(table)
+                .bind(column)
+                .fetch_one(pool)
+                .await?;
+
+        if !exists {
+            info!("Adding column {}.{}", table, column);
+            sqlx::query(&format!(
+                "ALTER TABLE {} ADD COLUMN {} {}",
+                table, column, column_type
+            ))
+            .execute(pool)
+            .await?;
+        }
+    }
+    Ok(())
+}
@@ -9,6 +9,17 @@ pub async fn send_email(
     email_content: &str,
     subject_: Option<&str>,
     attachment: Option<Attachment>,
+) -> Result<CreateEmailResponse, resend_rs::Error> {
+    send_email_with_reply_to(resend, email_content, subject_, attachment, None).await
+}
+
+/// Like `send_email`, with replies going to `reply_to` instead of the sender.
+pub async fn send_email_with_reply_to(
+    resend: &Resend,
+    email_content: &str,
+    subject_: Option<&str>,
+    attachment: Option<Attachment>,
+    reply_to: Option<&str>,
 ) -> Result<CreateEmailResponse, resend_rs::Error> {
     let from = "devnull03 <dev@dvnl.work>";
     let to = ["arnav@dvnl.work"];
@@ -21,6 +32,9 @@ pub async fn send_email(
     if let Some(attachment) = attachment {
         email = email.with_attachment(attachment);
     }
+    if let Some(reply_to) = reply_to {
+        email = email.with_reply(reply_to);
+    }
 
     let result = resend.emails.send(email).await;
     match &result {
@@ -31,7 +45,7 @@ pub async fn send_email(
     result
 }
 
-pub async fn _send_error_info(
+pub async fn send_error_info(
     resend: &Resend,
     error_info: &str,
 ) -> Result<CreateEmailResponse, resend_rs::Error> {
@@ -0,0 +1,242 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt::{self, Display},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use reqwest::{header::CONTENT_TYPE, Client};
+use resend_rs::Resend;
+use serde_json::json;
+use tracing::{error, info, warn};
+
+use crate::helpers::email;
+
+// discord rejects message content longer than 2000 chars
+const DISCORD_MAX_CONTENT: usize = 1900;
+
+/// Where an error happened, attached to every report so alerts are actionable.
+#[derive(Debug, Clone, Default)]
+pub struct ErrorContext {
+    pub route: Option<String>,
+    pub job: Option<String>,
+    pub payload_id: Option<String>,
+}
+
+impl ErrorContext {
+    pub fn route(route: &str) -> Self {
+        Self {
+            route: Some(route.to_string()),
+            ..Default::default()
+        }
+    }
+
+    pub fn job(job: &str) -> Self {
+        Self {
+            job: Some(job.to_string()),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_payload_id(mut self, payload_id: impl Into<String>) -> Self {
+        self.payload_id = Some(payload_id.into());
+        self
+    }
+
+    /// Payload ids are left out so the same failure on different payloads still dedupes.
+    fn fingerprint(&self) -> String {
+        format!(
+            "{}|{}",
+            self.route.as_deref().unwrap_or("-"),
+            self.job.as_deref().unwrap_or("-")
+        )
+    }
+}
+
+impl Display for ErrorContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "route: {}, job: {}, payload id: {}",
+            self.route.as_deref().unwrap_or("-"),
+            self.job.as_deref().unwrap_or("-"),
+            self.payload_id.as_deref().unwrap_or("-")
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ErrorReporterConfig {
+    /// Identical errors inside this window are counted but only alerted once.
+    pub dedupe_window: Duration,
+    /// Hard cap on alerts sent per hour, across all errors.
+    pub max_alerts_per_hour: usize,
+    pub discord_webhook_url: Option<String>,
+}
+
+impl Default for ErrorReporterConfig {
+    fn default() -> Self {
+        Self {
+            dedupe_window: Duration::from_secs(30 * 60),
+            max_alerts_per_hour: 10,
+            discord_webhook_url: None,
+        }
+    }
+}
+
+struct SeenError {
+    first_seen: Instant,
+    repeats: usize,
+}
+
+#[derive(Default)]
+struct ReporterState {
+    seen: HashMap<String, SeenError>,
+    sent: VecDeque<Instant>,
+    suppressed: usize,
+}
+
+struct PendingAlert {
+    repeats: usize,
+    suppressed: usize,
+}
+
+#[derive(Clone)]
+pub struct ErrorReporter {
+    resend: Resend,
+    http: Client,
+    config: ErrorReporterConfig,
+    state: Arc<Mutex<ReporterState>>,
+}
+
+impl ErrorReporter {
+    pub fn new(resend: Resend, config: ErrorReporterConfig) -> Self {
+        info!(
+            "Initializing error reporter (dedupe window: {:?}, max alerts/hour: {}, discord: {})",
+            config.dedupe_window,
+            config.max_alerts_per_hour,
+            config.discord_webhook_url.is_some()
+        );
+
+        Self {
+            resend,
+            http: Client::new(),
+            config,
+            state: Arc::new(Mutex::new(ReporterState::default())),
+        }
+    }
+
+    /// Logs the error and, unless it is a recent duplicate or the hourly
+    /// budget is spent, sends an alert by email and to discord if configured.
+    pub async fn report(&self, context: ErrorContext, error: impl Display) {
+        let message = error.to_string();
+        error!("{}: {}", context, message);
+
+        let Some(pending) = self.register(&context, &message) else {
+            return;
+        };
+
+        let mut body = format!(
+            "{}\n\nContext: {}\nTime: {}",
+            message,
+            context,
+            chrono::Utc::now().to_rfc3339()
+        );
+        if pending.repeats > 0 {
+            body.push_str(&format!(
+                "\nRepeated {} more time(s) in the previous window",
+                pending.repeats
+            ));
+        }
+        if pending.suppressed > 0 {
+            body.push_str(&format!(
+                "\n{} other alert(s) were suppressed by the rate limit",
+                pending.suppressed
+            ));
+        }
+
+        // send_error_info already logs its own failure, and there's nowhere left to report to
+        let _ = email::send_error_info(&self.resend, &body).await;
+
+        if let Some(url) = &self.config.discord_webhook_url {
+            self.send_discord(url, &body).await;
+        }
+    }
+
+    /// Fire-and-forget variant for call sites that shouldn't wait on the alert being sent.
+    pub fn report_in_background(&self, context: ErrorContext, error: impl Display) {
+        let reporter = self.clone();
+        let message = error.to_string();
+        tokio::spawn(async move { reporter.report(context, message).await });
+    }
+
+    fn register(&self, context: &ErrorContext, message: &str) -> Option<PendingAlert> {
+        let now = Instant::now();
+        let key = format!("{}|{}", context.fingerprint(), message);
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+
+        let window = self.config.dedupe_window;
+        while let Some(sent_at) = state.sent.front() {
+            if now.duration_since(*sent_at) < Duration::from_secs(60 * 60) {
+                break;
+            }
+            state.sent.pop_front();
+        }
+
+        if let Some(seen) = state.seen.get_mut(&key) {
+            if now.duration_since(seen.first_seen) < window {
+                seen.repeats += 1;
+                info!("Duplicate error suppressed ({} repeats): {}", seen.repeats, key);
+                return None;
+            }
+        }
+
+        if state.sent.len() >= self.config.max_alerts_per_hour {
+            state.suppressed += 1;
+            warn!("Error alert rate limit reached, suppressing alert");
+            return None;
+        }
+
+        let previous_repeats = state
+            .seen
+            .insert(
+                key,
+                SeenError {
+                    first_seen: now,
+                    repeats: 0,
+                },
+            )
+            .map(|seen| seen.repeats)
+            .unwrap_or(0);
+        // expired entries are only kept around while they still have repeats to report
+        state
+            .seen
+            .retain(|_, seen| seen.repeats > 0 || now.duration_since(seen.first_seen) < window);
+        state.sent.push_back(now);
+
+        Some(PendingAlert {
+            repeats: previous_repeats,
+            suppressed: std::mem::take(&mut state.suppressed),
+        })
+    }
+
+    async fn send_discord(&self, url: &str, body: &str) {
+        let content: String = format!("**Error from webhooks server**\n```\n{}\n```", body)
+            .chars()
+            .take(DISCORD_MAX_CONTENT)
+            .collect();
+
+        match self
+            .http
+            .post(url)
+            .header(CONTENT_TYPE, "application/json")
+            .body(json!({ "content": content }).to_string())
+            .send()
+            .await
+        {
+            Ok(resp) if resp.status().is_success() => info!("Error alert sent to discord"),
+            Ok(resp) => error!("Discord webhook returned status {}", resp.status()),
+            Err(e) => error!("Failed to send error alert to discord: {}", e),
+        }
+    }
+}
@@ -0,0 +1,213 @@
+use std::{
+    collections::HashSet,
+    fmt::Debug,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use anyhow::Context;
+use async_imap::Session;
+use mail_parser::MessageParser;
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    net::TcpStream,
+};
+use tokio_stream::StreamExt;
+use tracing::{info, warn};
+
+use crate::config::MailboxConfig;
+
+/// Plain tcp or tls, so a local IMAP server can be used without certificates.
+pub trait ImapStream: AsyncRead + AsyncWrite + Unpin + Send + Debug {}
+impl<T: AsyncRead + AsyncWrite + Unpin + Send + Debug> ImapStream for T {}
+
+/// A configured mailbox with its password resolved from the secrets.
+#[derive(Clone)]
+pub struct Mailbox {
+    pub config: MailboxConfig,
+    password: String,
+    /// Uids that came back without a body, left out of later polls so they
+    /// can't crowd out new mail. Forgotten on restart.
+    unreadable: Arc<Mutex<HashSet<u32>>>,
+}
+
+/// `lookup_secret` resolves each mailbox's `password_secret`, mailboxes without one are skipped.
+pub fn resolve_mailboxes(
+    configs: &[MailboxConfig],
+    lookup_secret: impl Fn(&str) -> Option<String>,
+) -> Vec<Mailbox> {
+    configs
+        .iter()
+        .filter_map(|config| match lookup_secret(&config.password_secret) {
+            Some(password) => Some(Mailbox {
+                config: config.clone(),
+                password,
+                unreadable: Arc::default(),
+            }),
+            None => {
+                warn!(
+                    "Mailbox '{}' has no password in secret {}, skipping",
+                    config.name, config.password_secret
+                );
+                None
+            }
+        })
+        .collect()
+}
+
+pub struct FetchedEmail {
+    pub uid: u32,
+    pub raw: Vec<u8>,
+}
+
+impl FetchedEmail {
+    /// Address in the From header, there's no envelope sender like with the webhook.
+    pub fn sender(&self) -> Option<String> {
+        MessageParser::default()
+            .parse_headers(&self.raw)
+            .and_then(|message| message.from()?.first()?.address().map(str::to_string))
+    }
+}
+
+pub struct MailboxSession<'a> {
+    mailbox: &'a Mailbox,
+    session: Session<Box<dyn ImapStream>>,
+}
+
+impl Mailbox {
+    pub async fn connect(&self) -> Result<MailboxSession<'_>, anyhow::Error> {
+        let config = &self.config;
+        info!(
+            "Connecting to {}:{} for mailbox '{}'",
+            config.host, config.port, config.name
+        );
+
+        let tcp = TcpStream::connect((config.host.as_str(), config.port))
+            .await
+            .with_context(|| format!("failed to connect to {}", config.host))?;
+        let stream: Box<dyn ImapStream> = if config.tls {
+            Box::new(
+                async_native_tls::TlsConnector::new()
+                    .connect(&config.host, tcp)
+                    .await?,
+            )
+        } else {
+            Box::new(tcp)
+        };
+
+        let mut session = async_imap::Client::new(stream)
+            .login(&config.username, &self.password)
+            .await
+            .map_err(|(e, _)| e)
+            .with_context(|| format!("failed to log in to mailbox '{}'", config.name))?;
+        session
+            .select(&config.folder)
+            .await
+            .with_context(|| format!("failed to open folder {}", config.folder))?;
+
+        Ok(MailboxSession {
+            mailbox: self,
+            session,
+        })
+    }
+}
+
+impl MailboxSession<'_> {
+    /// Messages matching the mailbox's search, oldest first, and the uids of
+    /// the ones that came back without a body, which later polls skip.
+    /// Fetched with BODY.PEEK so nothing is marked as seen until `mark_handled`.
+    pub async fn fetch_new(&mut self) -> Result<(Vec<FetchedEmail>, Vec<u32>), anyhow::Error> {
+        let config = &self.mailbox.config;
+
+        let mut uids: Vec<u32> = self
+            .session
+            .uid_search(&config.search)
+            .await?
+            .into_iter()
+            .collect();
+        {
+            let skipped = self.unreadable();
+            uids.retain(|uid| !skipped.contains(uid));
+        }
+        uids.sort_unstable();
+        uids.truncate(config.max_messages);
+
+        if uids.is_empty() {
+            return Ok((Vec::new(), Vec::new()));
+        }
+
+        let fetches: Vec<_> = self
+            .session
+            .uid_fetch(uid_set(&uids), "(UID BODY.PEEK[])")
+            .await?
+            .collect::<Result<_, _>>()
+            .await?;
+
+        let mut emails = Vec::new();
+        for fetch in &fetches {
+            match (fetch.uid, fetch.body()) {
+                (Some(uid), Some(body)) => emails.push(FetchedEmail {
+                    uid,
+                    raw: body.to_vec(),
+                }),
+                (Some(uid), None) => warn!(
+                    "Message {} in mailbox '{}' came back without a body",
+                    uid, config.name
+                ),
+                (None, _) => warn!(
+                    "Message {} in mailbox '{}' came back without a uid",
+                    fetch.message, config.name
+                ),
+            }
+        }
+
+        // never marked handled, so they'd match the search on every poll
+        let unreadable: Vec<u32> = uids
+            .into_iter()
+            .filter(|uid| !emails.iter().any(|email| email.uid == *uid))
+            .collect();
+        self.unreadable().extend(&unreadable);
+        Ok((emails, unreadable))
+    }
+
+    fn unreadable(&self) -> MutexGuard<'_, HashSet<u32>> {
+        self.mailbox
+            .unreadable
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Moves handled messages to `move_to`, or marks them as seen when it isn't set.
+    pub async fn mark_handled(&mut self, uids: &[u32]) -> Result<(), anyhow::Error> {
+        if uids.is_empty() {
+            return Ok(());
+        }
+
+        match &self.mailbox.config.move_to {
+            Some(folder) => self.session.uid_mv(uid_set(uids), folder).await?,
+            None => {
+                self.session
+                    .uid_store(uid_set(uids), "+FLAGS (\\Seen)")
+                    .await?
+                    .collect::<Result<Vec<_>, _>>()
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn logout(mut self) {
+        if let Err(e) = self.session.logout().await {
+            warn!(
+                "Failed to log out of mailbox '{}': {}",
+                self.mailbox.config.name, e
+            );
+        }
+    }
+}
+
+fn uid_set(uids: &[u32]) -> String {
+    uids.iter()
+        .map(u32::to_string)
+        .collect::<Vec<_>>()
+        .join(",")
+}
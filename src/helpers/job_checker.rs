@@ -1,140 +1,413 @@
-use mail_parser::{Message, MessageParser};
-use regex::Regex;
-use std::collections::HashMap;
+use chrono::{Duration, Utc};
+use sqlx::SqlitePool;
+use std::collections::{HashMap, HashSet};
+use tracing::info;
 
-use crate::models::job::{JobAlertSource, ParsedJob};
+use crate::{
+    config::{FilterConfig, MatchingConfig},
+    helpers::job_store,
+    models::{
+        job::{JobPosting, MergedJob, ParsedJob},
+        posting::{FilterRule, JobAnalysis, PostingUpdate, StoredPosting},
+    },
+};
+
+pub mod alerts;
+pub mod analysis;
+pub mod boards;
+pub mod commands;
+pub mod filters;
+pub mod links;
+pub mod matching;
+pub mod workday;
+
+use alerts::AlertEmail;
+use analysis::AnalysisConfig;
 
 pub mod scheduler {
-    use anyhow::Ok;
-    use tracing::info;
+    use anyhow::anyhow;
+    use reqwest::Url;
+    use tracing::{info, warn};
+
+    use crate::{config::OptumConfig, models::job::*};
+
+    use super::links::{self, CanonicalLink};
+
+    const OPTUM_API: &str = "https://jobsapi-internal.m-cloud.io/api/job";
+    const OPTUM_CALLBACK: &str = "CWS.jobs.jobCallback";
 
-    use crate::models::job::*;
+    /// Every page of an Optum search, up to `max_pages`.
+    pub async fn optum(config: &OptumConfig) -> Result<Vec<optum::Job>, anyhow::Error> {
+        let client = reqwest::Client::new();
+        let mut jobs = Vec::new();
+        let mut total_hits = 0;
 
-    pub async fn optum() -> Result<Vec<optum::Job>, anyhow::Error> {
-        info!("fetching optum jobs page api");
-        // let url = "https://jobsapi-internal.m-cloud.io/api/job?callback=CWS.jobs.jobCallback&facet[]=multi_select1:Technology&facet[]=level:Student Internships&facet[]=ats_portalid:Smashfly&latitude=28.4594965&longitude=77.0266383&LocationRadius=25&Limit=10&Organization=2071&offset=1&useBooleanKeywordSearch=true";
-        let url = "https://jobsapi-internal.m-cloud.io/api/job?callback=CWS.jobs.jobCallback&facet[]=parent_category:Optum&facet[]=level:Student Internships&facet[]=ats_portalid:Smashfly&latitude=28.4594965&longitude=77.0266383&LocationRadius=50&Limit=10&Organization=2071&offset=1&useBooleanKeywordSearch=true";
-        let body = reqwest::get(url).await?.text().await?;
+        // pages are numbered from 1
+        for page in 1..=config.max_pages {
+            info!("fetching optum jobs page {}", page);
+            let response = optum_page(&client, config, page).await?;
+            total_hits = response.total_hits as usize;
+
+            let found = response.query_result.len();
+            jobs.extend(response.query_result);
+            if found == 0 || jobs.len() >= total_hits {
+                break;
+            }
+        }
+
+        if jobs.len() < total_hits {
+            warn!(
+                "stopped after {} pages with {} of {} optum jobs",
+                config.max_pages,
+                jobs.len(),
+                total_hits
+            );
+        }
+        info!("parsed {} results from optum", jobs.len());
+        Ok(jobs)
+    }
+
+    async fn optum_page(
+        client: &reqwest::Client,
+        config: &OptumConfig,
+        page: u32,
+    ) -> Result<optum::JobResponse, anyhow::Error> {
+        let mut url = Url::parse(OPTUM_API)?;
+        {
+            let mut query = url.query_pairs_mut();
+            query.append_pair("callback", OPTUM_CALLBACK);
+            for category in &config.categories {
+                query.append_pair("facet[]", &format!("parent_category:{}", category));
+            }
+            for level in &config.levels {
+                query.append_pair("facet[]", &format!("level:{}", level));
+            }
+            // the careers site only lists postings from this portal
+            query.append_pair("facet[]", "ats_portalid:Smashfly");
+            if let Some(near) = &config.near {
+                query.append_pair("latitude", &near.latitude.to_string());
+                query.append_pair("longitude", &near.longitude.to_string());
+                query.append_pair("LocationRadius", &near.radius.to_string());
+            }
+            query.append_pair("Limit", &config.page_size.to_string());
+            query.append_pair("Organization", &config.organization.to_string());
+            query.append_pair("offset", &page.to_string());
+            query.append_pair("useBooleanKeywordSearch", "true");
+        }
+
+        let body = client
+            .get(url)
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
 
         let clean_body = body
-            .strip_prefix("CWS.jobs.jobCallback(")
-            .unwrap()
-            .strip_suffix(")")
-            .unwrap()
-            .to_string();
+            .strip_prefix(&format!("{}(", OPTUM_CALLBACK))
+            .and_then(|body| body.strip_suffix(")"))
+            .ok_or_else(|| {
+                anyhow!(
+                    "unexpected optum response format: {}",
+                    body.chars().take(200).collect::<String>()
+                )
+            })?;
+
+        Ok(serde_json::from_str(clean_body)?)
+    }
 
-        info!("parsed results from optum");
-        let job_response: optum::JobResponse = serde_json::from_str(&clean_body)?;
+    /// Falls back to the posting id when the url doesn't parse.
+    pub fn optum_link(job: &optum::Job) -> CanonicalLink {
+        links::canonicalize(&job.internal_url).unwrap_or_else(|| CanonicalLink {
+            site: None,
+            job_id: Some(job.id.to_string()),
+            key: format!("optum:{}", job.id),
+            url: job.internal_url.clone(),
+        })
+    }
 
-        Ok(job_response.query_result)
-        // Ok(serde_json::to_string(&job_response)?)
+    pub fn optum_posting(job: &optum::Job) -> JobPosting {
+        let link = optum_link(job);
+        JobPosting {
+            source: "optum".to_string(),
+            key: link.key,
+            title: job.title.clone(),
+            company: Some(job.company_name.clone()),
+            location: job.location(),
+            salary: None,
+            link: link.url,
+            description: Some(job.internal_description.clone()),
+            deadline: None,
+        }
     }
 }
 
-#[derive(Default)]
-pub struct JobAlertEmailHandler {
-    parsed_jobs: HashMap<String, ParsedJob>,
+/// Merges postings of the same job within a batch, without looking at what
+/// was seen before. Used for dry runs.
+pub fn merge_batch(postings: Vec<JobPosting>, config: &MatchingConfig) -> Vec<MergedJob> {
+    matching::cluster(postings, config)
+        .into_iter()
+        .map(matching::merge)
+        .collect()
 }
 
-impl JobAlertEmailHandler {
-    pub fn results(&self) -> &HashMap<String, ParsedJob> {
-        &self.parsed_jobs
+/// Merges postings of the same job within a batch, and with postings from
+/// other sources seen in the last `lookback_days`, so a job that showed up on
+/// LinkedIn yesterday and on Indeed today lists both links. The batch should
+/// already be recorded, so later runs can match against it.
+pub async fn merge_with_seen(
+    db: &SqlitePool,
+    config: &MatchingConfig,
+    postings: Vec<JobPosting>,
+) -> Result<Vec<MergedJob>, anyhow::Error> {
+    let since = Utc::now() - Duration::days(config.lookback_days.into());
+    let seen = job_store::seen_since(db, since).await?;
+
+    let mut groups = matching::cluster(postings, config);
+    for group in &mut groups {
+        // only other sources, the same site reposting a job isn't another way to apply
+        let matches: Vec<JobPosting> = seen
+            .iter()
+            .filter(|stored| {
+                group
+                    .iter()
+                    .all(|posting| posting.source != stored.source && posting.key != stored.key)
+                    && group
+                        .iter()
+                        .any(|posting| matching::is_same_job(posting, stored, config))
+            })
+            .cloned()
+            .collect();
+        for stored in matches {
+            if group.iter().all(|posting| posting.source != stored.source) {
+                info!(
+                    "{} ({}) matches {}",
+                    group[0].key, group[0].source, stored.key
+                );
+                group.push(stored);
+            }
+        }
     }
 
-    pub fn new(email_content: &[u8]) -> Self {
+    Ok(groups.into_iter().map(matching::merge).collect())
+}
 
-        let mut data = Self {
-            parsed_jobs: HashMap::new()
-        };
+/// Stores a feed's latest poll, closing what's gone from it when the poll got
+/// everything. Returns the keys of the postings that are new or changed, the
+/// ones worth reporting, and what changed about saved postings.
+pub async fn track_feed(
+    db: &SqlitePool,
+    feed: &str,
+    postings: &[JobPosting],
+    complete: bool,
+) -> Result<(HashSet<String>, Vec<PostingUpdate>), anyhow::Error> {
+    let (new, mut revisions) = job_store::record(db, postings, Some(feed)).await?;
+    let changed: HashSet<String> = new
+        .into_iter()
+        .chain(revisions.iter().map(|revision| revision.key.clone()))
+        .collect();
+    // an empty poll is more likely a broken search than every job closing at once
+    if complete && !postings.is_empty() {
+        for closed in job_store::close_missing(db, feed, postings).await? {
+            info!("{} closed: {}", feed, closed.key);
+            revisions.push(closed);
+        }
+    }
 
-        let parsed_email = MessageParser::default().parse(&email_content).unwrap();
+    let mut updates: Vec<PostingUpdate> = Vec::new();
+    for revision in revisions {
+        if let Some(update) = updates.iter_mut().find(|u| u.posting.key == revision.key) {
+            update.revisions.push(revision);
+            continue;
+        }
+        match job_store::get(db, &revision.key).await? {
+            Some(posting) if posting.saved_at.is_some() => updates.push(PostingUpdate {
+                posting,
+                revisions: vec![revision],
+            }),
+            _ => {}
+        }
+    }
+    Ok((changed, updates))
+}
 
-        println!(
-            "email from : {:?}, to: {:?} \n subject: {:?}",
-            parsed_email.from().unwrap(),
-            parsed_email.to().unwrap(),
-            parsed_email.subject().unwrap()
-        );
+/// Analyzes the postings that have a description, keyed by posting. The
+/// results are stored on the recorded postings when `save` is set.
+pub async fn analyze_postings(
+    db: &SqlitePool,
+    config: &AnalysisConfig,
+    postings: &[JobPosting],
+    save: bool,
+) -> Result<HashMap<String, JobAnalysis>, anyhow::Error> {
+    let mut analyses = HashMap::new();
+    for posting in postings {
+        let Some(description) = &posting.description else {
+            continue;
+        };
+        let analysis = analysis::analyze(description, config);
+        if save {
+            job_store::save_analysis(db, &posting.key, &analysis).await?;
+        }
+        analyses.insert(posting.key.clone(), analysis);
+    }
+    Ok(analyses)
+}
 
-        if let Some(body) = parsed_email.body_text(0) {
-            let re = Regex::new(r"jobalerts-noreply@linkedin\.com").unwrap();
-            if re.is_match(&body) {
-                data.parse_linkedin_email(&parsed_email);
+/// Drops postings a hide rule or the filters rule out, logging why. The
+/// filters only apply to postings with an analysis.
+pub fn filter_postings(
+    postings: Vec<JobPosting>,
+    analyses: &HashMap<String, JobAnalysis>,
+    filters: &FilterConfig,
+    rules: &[FilterRule],
+) -> Vec<JobPosting> {
+    postings
+        .into_iter()
+        .filter(|posting| {
+            let rejection = filters::hiding_rule(posting, rules)
+                .map(|rule| format!("hidden {} {}", rule.kind, rule.value))
+                .or_else(|| {
+                    analyses
+                        .get(&posting.key)
+                        .and_then(|analysis| filters::rejection(analysis, filters))
+                });
+            if let Some(reason) = &rejection {
+                info!("skipping {}: {}", posting.key, reason);
             }
+            rejection.is_none()
+        })
+        .collect()
+}
 
-            let re = Regex::new(r"Glassdoor Jobs <noreply@glassdoor\.com>").unwrap();
-            if re.is_match(&body) {
-                data.parse_glassdoor_email(&parsed_email);
-            }
-        }
-        data
+/// Notification body for saved postings that changed or closed.
+pub fn updates_html(updates: &[PostingUpdate]) -> String {
+    let mut content = String::from("<h2>Saved Jobs Changed</h2>");
+    for update in updates {
+        let changes: Vec<String> = update
+            .revisions
+            .iter()
+            .map(|revision| match revision.field.as_str() {
+                "status" => revision.new_value.clone().unwrap_or_default(),
+                // descriptions are too long to show
+                "description" => "description changed".to_string(),
+                field => format!(
+                    "{} changed from {} to {}",
+                    field,
+                    revision.old_value.as_deref().unwrap_or("nothing"),
+                    revision.new_value.as_deref().unwrap_or("nothing")
+                ),
+            })
+            .collect();
+        content.push_str(&format!(
+            "<p>{}: {}</p>",
+            posting_html(&update.posting),
+            changes.join(", ")
+        ));
     }
+    content
+}
 
-    fn parse_linkedin_email(&mut self, parsed_email: &Message) {
-        // let mut jobs: HashMap<String, ParsedJob> = HashMap::new();
-
-        if let Some(body) = parsed_email.body_text(0) {
-            let re = Regex::new(
-            r"(?<line_before>[^\n]+)\n<https://www\.linkedin\.com/comm/jobs/view/(?<job_id>\d{10})",
-        )
-        .unwrap();
-
-            for caps in re.captures_iter(&body) {
-                let line_before_url = caps["line_before"].trim().to_string();
-                let job_id = caps["job_id"].to_string();
-                let job_link = format!("https://www.linkedin.com/comm/jobs/view/{}", &job_id);
-
-                if line_before_url.starts_with("[image:")
-                    || line_before_url.starts_with("Actively recruiting")
-                    || line_before_url.ends_with("connection")
-                    || line_before_url.is_empty()
-                {
-                    continue;
-                }
-
-                println!("Found LinkedIn job:");
-                println!("  Line before URL: {}", line_before_url);
-                println!("  Job ID: {}", job_id);
-                println!(
-                    "  Full URL: https://www.linkedin.com/comm/jobs/view/{}",
-                    job_id
-                );
-                println!();
-
-                self.parsed_jobs.insert(
-                    job_id.clone(),
-                    ParsedJob {
-                        link: job_link,
-                        title: {
-                            if line_before_url.starts_with("*") && line_before_url.ends_with("*") {
-                                line_before_url.clone()
-                            } else {
-                                self.parsed_jobs.get(&job_id).unwrap().title.clone()
-                            }
-                        },
-                        location: {
-                            if !(line_before_url.starts_with("*") && line_before_url.ends_with("*"))
-                            {
-                                line_before_url
-                            } else {
-                                "".to_owned()
-                            }
-                        },
-                        source: JobAlertSource::Linkedin,
-                        job_id,
-                    },
-                );
-            }
-        }
+/// Notification body for saved postings whose deadline is coming up.
+pub fn closing_html(postings: &[StoredPosting]) -> String {
+    let mut content = String::from("<h2>Saved Jobs Closing Soon</h2>");
+    for posting in postings {
+        content.push_str(&format!(
+            "<p>{}: apply by {}</p>",
+            posting_html(posting),
+            posting.deadline.as_deref().unwrap_or("soon")
+        ));
+    }
+    content
+}
 
-        // println!("jobs found: {:?}", &jobs);
+/// Notification body for applications that haven't heard back in a while.
+pub fn stale_html(postings: &[StoredPosting]) -> String {
+    let mut content = String::from("<h2>Applications Gone Quiet</h2>");
+    for posting in postings {
+        // rfc3339, the date is all that's worth showing
+        let since = posting
+            .status_changed_at
+            .as_deref()
+            .and_then(|changed_at| changed_at.get(..10))
+            .unwrap_or("a while");
+        content.push_str(&format!(
+            "<p>{}: {} since {}</p>",
+            posting_html(posting),
+            posting.status.as_deref().unwrap_or_default(),
+            since
+        ));
     }
+    content
+}
+
+fn posting_html(posting: &StoredPosting) -> String {
+    format!(
+        "<strong>{}</strong> at {} (<a href=\"{}\">{}</a>)",
+        posting.title,
+        posting.company.as_deref().unwrap_or("unknown company"),
+        posting.link,
+        posting.source
+    )
+}
 
-    fn parse_glassdoor_email(&self, _parsed_email: &Message) {
-        unimplemented!();
+/// Notification body, one entry per job with a link for every source. Jobs
+/// are numbered from 1 so replies can refer to them.
+pub fn jobs_html(jobs: &[MergedJob]) -> String {
+    let mut content = String::from("<h2>Found Jobs</h2>");
+    for (i, job) in jobs.iter().enumerate() {
+        let links: Vec<String> = job
+            .postings
+            .iter()
+            .map(|posting| format!("<a href=\"{}\">{}</a>", posting.link, posting.source))
+            .collect();
+        content.push_str(&format!(
+            "<p>[{}] <strong>{}</strong> at {} ({}){}: {}</p>",
+            i + 1,
+            job.title,
+            job.company.as_deref().unwrap_or("unknown company"),
+            job.location,
+            job.salary
+                .as_deref()
+                .map(|salary| format!(", {}", salary))
+                .unwrap_or_default(),
+            links.join(" | ")
+        ));
     }
+    content
 }
 
+#[derive(Default)]
+pub struct JobAlertEmailHandler {
+    parsed_jobs: HashMap<String, ParsedJob>,
+}
 
+impl JobAlertEmailHandler {
+    pub fn results(&self) -> &HashMap<String, ParsedJob> {
+        &self.parsed_jobs
+    }
 
+    pub fn postings(&self) -> Vec<JobPosting> {
+        self.parsed_jobs.values().map(JobPosting::from).collect()
+    }
 
+    pub fn new(email_content: &[u8]) -> Result<Self, anyhow::Error> {
+        let mut data = Self {
+            parsed_jobs: HashMap::new(),
+        };
+
+        let email = AlertEmail::parse(email_content)?;
+
+        info!(
+            "email from: {}, to: {}, subject: {}",
+            email.sender.as_deref().unwrap_or("<unknown>"),
+            email.recipient.as_deref().unwrap_or("<unknown>"),
+            email.subject.as_deref().unwrap_or("<none>")
+        );
+
+        for job in alerts::parse_jobs(&email) {
+            info!("Found {:?} job {}: {}", job.source, job.job_id, job.title);
+            data.parsed_jobs.insert(job.key.clone(), job);
+        }
+        Ok(data)
+    }
+}
@@ -0,0 +1,66 @@
+use super::{
+    flattened_html, job_blocks, non_empty_lines, parse_date, search_term, AlertEmail, JobBlock,
+    JobTags, Site,
+};
+use crate::models::job::{JobAlertSource, ParsedJob};
+
+/// Handshake saved search alerts list each job as
+///
+/// ```text
+/// *Software Developer Co-op*
+/// <https://app.joinhandshake.com/stu/jobs/9012345?ref=saved-search-email>
+/// Shopify
+/// Co-op · Paid · Full-Time
+/// Toronto, ON · Hybrid
+/// Apply by May 30, 2025
+/// ```
+///
+/// with the employer's logo linking to its profile in between jobs.
+pub fn parse(email: &AlertEmail) -> Vec<ParsedJob> {
+    let text = flattened_html(email);
+    let lines = non_empty_lines(&text);
+    let search_term = search_term(email);
+
+    job_blocks(&lines, Site::Handshake)
+        .iter()
+        .map(|block| job_from_block(block, email, &search_term))
+        .collect()
+}
+
+fn job_from_block(block: &JobBlock, email: &AlertEmail, search_term: &Option<String>) -> ParsedJob {
+    let mut details = block.details.iter();
+    // the employer always comes right after the title
+    let employer = details.next().map(|line| line.to_string());
+
+    let mut tags = JobTags::default();
+    let mut deadline = None;
+    for line in details {
+        if let Some(date) = strip_prefix_ignore_case(line, "apply by") {
+            deadline = deadline.or_else(|| parse_date(date.trim_start_matches(':'), email.date));
+        } else {
+            tags.add(line);
+        }
+    }
+
+    ParsedJob {
+        source: JobAlertSource::Handshake,
+        job_id: block.job_id.clone(),
+        key: block.link.key.clone(),
+        title: block.title.to_string(),
+        company: employer,
+        location: tags.location.unwrap_or_default(),
+        workplace: tags.workplace,
+        salary: tags.salary,
+        job_type: tags.job_type,
+        deadline,
+        signals: Vec::new(),
+        search_term: search_term.clone(),
+        link: block.link.url.clone(),
+    }
+}
+
+fn strip_prefix_ignore_case<'a>(line: &'a str, prefix: &str) -> Option<&'a str> {
+    line.get(..prefix.len())
+        .filter(|head| head.eq_ignore_ascii_case(prefix))
+        .map(|_| &line[prefix.len()..])
+}
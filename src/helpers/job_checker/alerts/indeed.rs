@@ -0,0 +1,103 @@
+use regex::Regex;
+
+use super::{flattened_html, job_blocks, non_empty_lines, search_term, AlertEmail, JobBlock, Site};
+use crate::models::job::{JobAlertSource, JobSignal, ParsedJob, WorkplaceType};
+
+/// Indeed's plain text part doesn't link every field, so this works off the html
+/// part, where a job's block looks like
+///
+/// ```text
+/// *Rust Developer*
+/// <https://ca.indeed.com/rc/clk/dl?jk=8f1c2b7a9d3e4f50&from=ja&...>
+/// Acme Corp
+/// Hybrid remote in Toronto, ON
+/// $90,000–$120,000 a year
+/// Easily apply
+/// ```
+///
+/// followed by a snippet and how long ago it was posted.
+pub fn parse(email: &AlertEmail) -> Vec<ParsedJob> {
+    let text = flattened_html(email);
+    let lines = non_empty_lines(&text);
+
+    let search_term = search_term(email).or_else(|| subject_search_term(email));
+    let salary_re = Regex::new(
+        r"(?i)^(?:from\s+|up to\s+)?[$€£]\s?\d[\d,.]*k?(?:\s*[–-]\s*[$€£]?\s?\d[\d,.]*k?)?\s+(?:an?|per)\s+(?:hour|day|week|month|year)$",
+    )
+    .unwrap();
+
+    job_blocks(&lines, Site::Indeed)
+        .iter()
+        .map(|block| job_from_block(block, &search_term, &salary_re))
+        .collect()
+}
+
+fn job_from_block(block: &JobBlock, search_term: &Option<String>, salary_re: &Regex) -> ParsedJob {
+    let mut company = None;
+    let mut location = String::new();
+    let mut workplace = None;
+    let mut salary = None;
+    let mut signals = Vec::new();
+
+    // company then location come first, the rest can show up in any order
+    for (n, line) in block.details.iter().enumerate() {
+        if let Some(signal) = parse_signal(line) {
+            signals.push(signal);
+        } else if salary_re.is_match(line) {
+            salary = salary.or(Some(line.to_string()));
+        } else if n == 0 {
+            company = Some(line.to_string());
+        } else if n == 1 {
+            (location, workplace) = parse_location(line);
+        }
+    }
+
+    ParsedJob {
+        source: JobAlertSource::Indeed,
+        job_id: block.job_id.clone(),
+        key: block.link.key.clone(),
+        title: block.title.to_string(),
+        company,
+        location,
+        workplace,
+        salary,
+        job_type: None,
+        deadline: None,
+        signals,
+        search_term: search_term.clone(),
+        link: block.link.url.clone(),
+    }
+}
+
+/// `Remote in Toronto, ON`, `Hybrid remote in Toronto, ON` or just `Remote`,
+/// Indeed doesn't label on-site jobs.
+fn parse_location(line: &str) -> (String, Option<WorkplaceType>) {
+    let lower = line.to_lowercase();
+    for (prefix, workplace) in [
+        ("hybrid remote in ", WorkplaceType::Hybrid),
+        ("remote in ", WorkplaceType::Remote),
+    ] {
+        if lower.starts_with(prefix) {
+            return (line[prefix.len()..].trim().to_string(), Some(workplace));
+        }
+    }
+    (line.to_string(), WorkplaceType::from_label(line))
+}
+
+fn parse_signal(line: &str) -> Option<JobSignal> {
+    match line.to_lowercase().as_str() {
+        "easily apply" => Some(JobSignal::EasyApply),
+        "urgently hiring" => Some(JobSignal::ActivelyRecruiting),
+        _ => None,
+    }
+}
+
+/// Indeed subjects look like `12 new rust developer jobs in Toronto, ON`.
+fn subject_search_term(email: &AlertEmail) -> Option<String> {
+    let subject = email.subject.as_deref()?;
+    let re =
+        Regex::new(r"(?i)^(?:\d+\+?\s+)?(?:new\s+)?(?<term>.+?)\s+jobs?\s+(?:in|near)\b").unwrap();
+    re.captures(subject)
+        .map(|caps| caps["term"].trim().to_string())
+        .filter(|term| !term.is_empty())
+}
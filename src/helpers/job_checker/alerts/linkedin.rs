@@ -0,0 +1,196 @@
+use regex::Regex;
+use tracing::warn;
+
+use super::{search_term, AlertEmail, CanonicalLink, Site};
+use crate::models::job::{JobAlertSource, JobSignal, ParsedJob, WorkplaceType};
+
+/// Every line of a job card links to the same job, so the text part is a run of
+///
+/// ```text
+/// [image: Hitachi Rail]
+/// <https://www.linkedin.com/comm/jobs/view/4245554985/?trackingId=...>
+///
+/// *Software Analyst Intern*
+/// <https://www.linkedin.com/comm/jobs/view/4245554985/?trackingId=...>
+///
+/// Hitachi Rail · Toronto, ON (Hybrid)
+/// <https://www.linkedin.com/comm/jobs/view/4245554985/?trackingId=...>
+/// ```
+///
+/// followed by optional signal lines. Lines are grouped by job id and fed through
+/// `Card` in order.
+pub fn parse(email: &AlertEmail) -> Vec<ParsedJob> {
+    let re = Regex::new(
+        r"(?m)^(?<label>[^\n]*)\n<https://(?:[a-z]+\.)?linkedin\.com/comm/jobs/view/(?<job_id>\d+)",
+    )
+    .unwrap();
+
+    let search_term = search_term(email);
+    let mut jobs = Vec::new();
+    let mut card: Option<Card> = None;
+
+    for caps in re.captures_iter(&email.text) {
+        let job_id = &caps["job_id"];
+        let label = caps["label"].trim_matches(|c: char| c.is_whitespace() || c == '\u{200b}');
+
+        if card.as_ref().is_none_or(|card| card.job_id != job_id) {
+            jobs.extend(card.take().and_then(|card| card.finish(&search_term)));
+            card = Some(Card::new(job_id));
+        }
+        if let Some(card) = card.as_mut() {
+            card.push(label);
+        }
+    }
+    jobs.extend(card.and_then(|card| card.finish(&search_term)));
+
+    jobs
+}
+
+#[derive(Debug, PartialEq)]
+enum CardState {
+    /// Only the company logo so far
+    Start,
+    /// Got the title, the company and location line should come next
+    Titled,
+    /// Got the company and location, anything after is a signal
+    Described,
+}
+
+struct Card {
+    job_id: String,
+    state: CardState,
+    title: Option<String>,
+    logo: Option<String>,
+    company: Option<String>,
+    location: String,
+    workplace: Option<WorkplaceType>,
+    signals: Vec<JobSignal>,
+}
+
+impl Card {
+    fn new(job_id: &str) -> Self {
+        Self {
+            job_id: job_id.to_string(),
+            state: CardState::Start,
+            title: None,
+            logo: None,
+            company: None,
+            location: String::new(),
+            workplace: None,
+            signals: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, label: &str) {
+        if label.is_empty() {
+            return;
+        }
+
+        if let Some(signal) = parse_signal(label) {
+            self.signals.push(signal);
+            return;
+        }
+
+        if let Some(alt) = label
+            .strip_prefix("[image:")
+            .and_then(|alt| alt.strip_suffix(']'))
+        {
+            // the logo comes first, later images are connections' profile pictures
+            if self.state == CardState::Start {
+                self.logo = Some(alt.trim().to_string());
+            }
+            return;
+        }
+
+        // the title is normally first, but don't count on it
+        if is_emphasized(label) {
+            if self.title.is_none() {
+                self.title = Some(label.trim_matches('*').trim().to_string());
+                if self.state == CardState::Start {
+                    self.state = CardState::Titled;
+                }
+            } else {
+                warn!(
+                    "Second title in LinkedIn job card {}: {}",
+                    self.job_id, label
+                );
+            }
+            return;
+        }
+
+        match self.state {
+            CardState::Start | CardState::Titled => {
+                self.describe(label);
+                self.state = CardState::Described;
+            }
+            CardState::Described => {
+                warn!(
+                    "Unexpected line in LinkedIn job card {}: {}",
+                    self.job_id, label
+                );
+            }
+        }
+    }
+
+    /// `Company · Location (Workplace)`, where the company can be missing.
+    fn describe(&mut self, label: &str) {
+        let (company, location) = match label.split_once(" · ") {
+            Some((company, location)) => (Some(company.trim()), location.trim()),
+            None => (None, label.trim()),
+        };
+
+        let (location, workplace) = match location.rsplit_once(" (").and_then(|(place, kind)| {
+            Some((place, WorkplaceType::from_label(kind.strip_suffix(')')?)?))
+        }) {
+            Some((place, workplace)) => (place.trim(), Some(workplace)),
+            None => (location, None),
+        };
+
+        self.company = company.map(str::to_string);
+        self.location = location.to_string();
+        self.workplace = workplace;
+    }
+
+    fn finish(self, search_term: &Option<String>) -> Option<ParsedJob> {
+        let Some(title) = self.title else {
+            warn!("LinkedIn job card {} has no title, skipping", self.job_id);
+            return None;
+        };
+
+        let link = CanonicalLink::posting(Site::Linkedin, &self.job_id);
+        Some(ParsedJob {
+            source: JobAlertSource::Linkedin,
+            key: link.key,
+            link: link.url,
+            job_id: self.job_id,
+            title,
+            company: self.company.or(self.logo),
+            location: self.location,
+            workplace: self.workplace,
+            salary: None,
+            job_type: None,
+            deadline: None,
+            signals: self.signals,
+            search_term: search_term.clone(),
+        })
+    }
+}
+
+fn is_emphasized(label: &str) -> bool {
+    label.len() > 2 && label.starts_with('*') && label.ends_with('*')
+}
+
+fn parse_signal(label: &str) -> Option<JobSignal> {
+    let lower = label.to_lowercase();
+    if lower == "actively recruiting" {
+        return Some(JobSignal::ActivelyRecruiting);
+    }
+    if lower == "easy apply" {
+        return Some(JobSignal::EasyApply);
+    }
+
+    let count = lower
+        .strip_suffix(" connections")
+        .or_else(|| lower.strip_suffix(" connection"))?;
+    count.trim().parse().ok().map(JobSignal::Connections)
+}
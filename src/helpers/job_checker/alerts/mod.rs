@@ -0,0 +1,450 @@
+use std::sync::LazyLock;
+
+use anyhow::anyhow;
+use chrono::{Datelike, NaiveDate};
+use mail_parser::{Message, MessageParser, PartType};
+use regex::Regex;
+use tracing::warn;
+
+use super::links::{self, CanonicalLink, Site};
+use crate::models::job::{JobAlertSource, JobType, ParsedJob, WorkplaceType};
+
+mod handshake;
+mod indeed;
+mod linkedin;
+mod wellfound;
+
+/// Lines after a job link that can still belong to that job, past that it's a
+/// description snippet or the email footer.
+const MAX_DETAIL_LINES: usize = 8;
+
+/// The parts of a job alert email the parsers care about, taken from the original
+/// alert rather than whatever forwarded it to us.
+#[derive(Debug, Default)]
+pub struct AlertEmail {
+    /// Address the alert was originally sent from, lowercased
+    pub sender: Option<String>,
+    pub recipient: Option<String>,
+    pub subject: Option<String>,
+    /// The text/plain part, or the text/html part flattened into the same shape
+    pub text: String,
+    pub html: Option<String>,
+    /// Day the email was sent, for dates the alert gives without a year
+    pub date: Option<NaiveDate>,
+}
+
+impl AlertEmail {
+    pub fn parse(raw: &[u8]) -> Result<Self, anyhow::Error> {
+        let message = MessageParser::default()
+            .parse(raw)
+            .ok_or_else(|| anyhow!("failed to parse email ({} bytes)", raw.len()))?;
+
+        // alerts forwarded as an attachment carry the original as message/rfc822
+        let message = innermost_message(&message);
+
+        let text = text_part(message);
+        let html = html_part(message);
+        let forwarded = text
+            .as_deref()
+            .and_then(ForwardedHeaders::find)
+            .unwrap_or_default();
+
+        let text = match (text, &html) {
+            (Some(text), _) if !text.trim().is_empty() => text,
+            (_, Some(html)) => html_to_text(html),
+            _ => String::new(),
+        };
+
+        Ok(Self {
+            sender: forwarded.from.or_else(|| header_sender(message)),
+            recipient: forwarded.to.or_else(|| {
+                message
+                    .to()
+                    .and_then(|to| to.first())
+                    .and_then(|to| to.address())
+                    .map(str::to_lowercase)
+            }),
+            subject: forwarded
+                .subject
+                .or_else(|| message.subject().map(str::to_string)),
+            text,
+            html,
+            date: message.date().and_then(|date| {
+                NaiveDate::from_ymd_opt(date.year as i32, date.month as u32, date.day as u32)
+            }),
+        })
+    }
+
+    pub fn source(&self) -> Option<JobAlertSource> {
+        self.sender.as_deref().and_then(JobAlertSource::from_sender)
+    }
+}
+
+/// Runs the parser for whichever job board sent the email.
+pub fn parse_jobs(email: &AlertEmail) -> Vec<ParsedJob> {
+    match email.source() {
+        Some(JobAlertSource::Linkedin) => linkedin::parse(email),
+        Some(JobAlertSource::Indeed) => indeed::parse(email),
+        Some(JobAlertSource::Handshake) => handshake::parse(email),
+        Some(JobAlertSource::Wellfound) => wellfound::parse(email),
+        Some(source) => {
+            warn!("No parser for {:?} alerts yet", source);
+            Vec::new()
+        }
+        None => {
+            warn!("Couldn't tell which job board sent this email");
+            Vec::new()
+        }
+    }
+}
+
+static SEARCH_TERM: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"\bfor\s+[“"'‘](?<term>[^”"'’]+)[”"'’]"#).unwrap());
+
+/// The saved search an alert is for, from subjects like `8 new jobs for “software coop”`.
+fn search_term(email: &AlertEmail) -> Option<String> {
+    let subject = email.subject.as_deref()?;
+    SEARCH_TERM
+        .captures(subject)
+        .map(|caps| caps["term"].trim().to_string())
+        .filter(|term| !term.is_empty())
+}
+
+/// The html part flattened by `html_to_text`, for boards whose plain text part
+/// leaves out the links.
+fn flattened_html(email: &AlertEmail) -> String {
+    match &email.html {
+        Some(html) => html_to_text(html),
+        None => email.text.clone(),
+    }
+}
+
+fn non_empty_lines(text: &str) -> Vec<&str> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect()
+}
+
+/// The url of a `<url>` line.
+fn as_link(line: &str) -> Option<&str> {
+    line.strip_prefix('<')?.strip_suffix('>')
+}
+
+/// A job as laid out by `html_to_text`: the linked title, then whatever plain
+/// lines follow it, e.g.
+///
+/// ```text
+/// *Rust Developer*
+/// <https://ca.indeed.com/rc/clk/dl?jk=8f1c2b7a9d3e4f50&from=ja&...>
+/// Acme Corp
+/// Hybrid remote in Toronto, ON
+/// ```
+#[derive(Debug)]
+struct JobBlock<'a> {
+    /// Index of the job link in the lines
+    index: usize,
+    job_id: String,
+    link: CanonicalLink,
+    title: &'a str,
+    details: Vec<&'a str>,
+}
+
+/// The posting a `<url>` line links to, if it's one on `site`.
+fn posting_link(line: &str, site: Site) -> Option<CanonicalLink> {
+    as_link(line)
+        .and_then(links::canonicalize)
+        .filter(|link| link.site == Some(site))
+}
+
+/// Splits non-empty lines into job blocks, one per posting on `site`. Any other
+/// link is treated as the edge of a block.
+fn job_blocks<'a>(lines: &[&'a str], site: Site) -> Vec<JobBlock<'a>> {
+    // (index of the job link, canonical link)
+    let mut anchors: Vec<(usize, CanonicalLink)> = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        let Some(link) = posting_link(line, site) else {
+            continue;
+        };
+        // the title and the apply button both link to the job
+        if anchors.last().is_some_and(|(_, last)| last.key == link.key) {
+            continue;
+        }
+        anchors.push((i, link));
+    }
+
+    let mut blocks = Vec::new();
+    for (n, (i, link)) in anchors.iter().enumerate() {
+        let i = *i;
+        let title = (i > 0)
+            .then(|| lines[i - 1])
+            .filter(|title| as_link(title).is_none())
+            .map(|title| title.trim_matches('*').trim())
+            .filter(|title| !title.is_empty());
+        let Some(title) = title else {
+            warn!("Job link {} has no title, skipping", link.url);
+            continue;
+        };
+
+        // stop before the next job's title
+        let end = anchors
+            .get(n + 1)
+            .map_or(lines.len(), |(next, _)| next - 1)
+            .min(i + 1 + MAX_DETAIL_LINES)
+            .max(i + 1);
+        let mut details = Vec::new();
+        for (j, line) in lines[i + 1..end].iter().enumerate() {
+            let is_label = lines
+                .get(i + 2 + j)
+                .is_some_and(|next| as_link(next).is_some());
+            if as_link(line).is_some() {
+                // a sponsored job or the footer, nothing after it belongs to this job
+                if posting_link(line, site).is_none_or(|other| other.key != link.key) {
+                    break;
+                }
+            } else if !is_label {
+                // link text like the apply button is skipped
+                details.push(*line);
+            }
+        }
+
+        blocks.push(JobBlock {
+            index: i,
+            job_id: link.job_id.clone().unwrap_or_default(),
+            link: link.clone(),
+            title,
+            details,
+        });
+    }
+
+    blocks
+}
+
+/// What boards list as `Internship · Paid · Toronto, ON · Hybrid · $25/hr`, in any order.
+#[derive(Debug, Default)]
+struct JobTags {
+    job_type: Option<JobType>,
+    workplace: Option<WorkplaceType>,
+    location: Option<String>,
+    salary: Option<String>,
+}
+
+impl JobTags {
+    /// Adds the tags from one line, the first of each kind wins.
+    fn add(&mut self, line: &str) {
+        for tag in line.split(['·', '•']).map(str::trim) {
+            let lower = tag.to_lowercase();
+            if tag.is_empty() || matches!(lower.as_str(), "paid" | "unpaid") || tag.contains('%') {
+                // equity ranges and the like aren't worth keeping
+                continue;
+            }
+
+            if let Some(job_type) = JobType::from_label(tag) {
+                self.job_type.get_or_insert(job_type);
+            } else if let Some(workplace) = WorkplaceType::from_label(tag) {
+                self.workplace.get_or_insert(workplace);
+            } else if tag.contains(['$', '€', '£']) && tag.contains(|c: char| c.is_ascii_digit())
+            {
+                self.salary.get_or_insert_with(|| tag.to_string());
+            } else {
+                self.location.get_or_insert_with(|| tag.to_string());
+            }
+        }
+    }
+}
+
+/// `May 30, 2025`, or `Jun 15` taken as the next one after the email was sent.
+fn parse_date(text: &str, sent: Option<NaiveDate>) -> Option<NaiveDate> {
+    let text = text.trim().trim_end_matches('.');
+    for format in ["%B %d, %Y", "%B %d %Y", "%Y-%m-%d"] {
+        if let Ok(date) = NaiveDate::parse_from_str(text, format) {
+            return Some(date);
+        }
+    }
+
+    let sent = sent?;
+    let in_year =
+        |year: i32| NaiveDate::parse_from_str(&format!("{} {}", text, year), "%B %d %Y").ok();
+    let date = in_year(sent.year())?;
+    if date < sent {
+        in_year(sent.year() + 1)
+    } else {
+        Some(date)
+    }
+}
+
+fn innermost_message<'a>(message: &'a Message<'a>) -> &'a Message<'a> {
+    message
+        .attachments()
+        .find_map(|part| part.message())
+        .map(innermost_message)
+        .unwrap_or(message)
+}
+
+fn text_part(message: &Message) -> Option<String> {
+    message
+        .text_body
+        .iter()
+        .filter_map(|id| message.part(*id))
+        .find_map(|part| match &part.body {
+            PartType::Text(text) => Some(text.to_string()),
+            _ => None,
+        })
+}
+
+fn html_part(message: &Message) -> Option<String> {
+    message
+        .html_body
+        .iter()
+        .filter_map(|id| message.part(*id))
+        .find_map(|part| match &part.body {
+            PartType::Html(html) => Some(html.to_string()),
+            _ => None,
+        })
+}
+
+/// From, then Sender, Reply-To and Return-Path for mail that lost its From header.
+fn header_sender(message: &Message) -> Option<String> {
+    [message.from(), message.sender(), message.reply_to()]
+        .into_iter()
+        .flatten()
+        .find_map(|address| address.first()?.address())
+        .map(str::to_string)
+        .or_else(|| {
+            message
+                .header_raw("Return-Path")
+                .map(|path| path.trim().trim_matches(['<', '>']).to_string())
+                .filter(|path| path.contains('@'))
+        })
+        .map(|address| address.to_lowercase())
+}
+
+/// Header block a mail client writes when forwarding inline, e.g.
+///
+/// ```text
+/// -------- Forwarded message --------
+/// Subject: 8 new jobs for “software coop”
+/// From: LinkedIn Job Alerts <jobalerts-noreply@linkedin.com>
+/// ```
+#[derive(Debug, Default)]
+struct ForwardedHeaders {
+    from: Option<String>,
+    to: Option<String>,
+    subject: Option<String>,
+}
+
+impl ForwardedHeaders {
+    const MAX_HEADER_LINES: usize = 20;
+
+    fn find(text: &str) -> Option<Self> {
+        let start = text.find("Forwarded message")?;
+        let mut headers = Self::default();
+
+        for line in text[start..]
+            .lines()
+            .skip(1)
+            .take(Self::MAX_HEADER_LINES)
+            .map(str::trim)
+        {
+            if let Some(from) = line.strip_prefix("From:") {
+                headers.from = headers.from.or_else(|| extract_address(from));
+            } else if let Some(to) = line.strip_prefix("To:") {
+                headers.to = headers.to.or_else(|| extract_address(to));
+            } else if let Some(subject) = line.strip_prefix("Subject:") {
+                headers.subject = headers.subject.or(Some(subject.trim().to_string()));
+            }
+        }
+
+        headers.from.is_some().then_some(headers)
+    }
+}
+
+/// `Name <a@b.c>` or a bare `a@b.c`, lowercased.
+fn extract_address(value: &str) -> Option<String> {
+    let value = value.trim();
+    let address = match (value.rfind('<'), value.rfind('>')) {
+        (Some(start), Some(end)) if start < end => &value[start + 1..end],
+        _ => value
+            .split_whitespace()
+            .find(|word| word.contains('@'))
+            .unwrap_or(value),
+    };
+    address.contains('@').then(|| address.trim().to_lowercase())
+}
+
+static HIDDEN_ELEMENT: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?is)<(style|script|head)[^>]*>.*?</(style|script|head)>").unwrap()
+});
+static IMAGE_ALT: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"(?is)<img[^>]*\balt="([^"]*)"[^>]*>"#).unwrap());
+static BOLD: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)</?(strong|b)(\s[^>]*)?>").unwrap());
+static ANCHOR: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"(?is)<a\s[^>]*?href="([^"]*)"[^>]*>(.*?)</a>"#).unwrap());
+static LINE_BREAK: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)<br\s*/?>|</(p|div|tr|li|h[1-6]|table)>").unwrap());
+static TAG: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?s)<[^>]*>").unwrap());
+static NUMERIC_REFERENCE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"&#(?:(?<dec>\d{1,7})|[xX](?<hex>[0-9a-fA-F]{1,6}));").unwrap());
+
+/// Flattens html into the same "text, then `<url>` on the next line" shape gmail
+/// uses for the plain text part, so the parsers only need to handle one format.
+pub fn html_to_text(html: &str) -> String {
+    let strip =
+        |re: &Regex, html: &str, replacement: &str| re.replace_all(html, replacement).into_owned();
+
+    let html = strip(&HIDDEN_ELEMENT, html, "");
+    let html = strip(&IMAGE_ALT, &html, "[image: $1]");
+    // bold text comes through as *text* in the plain text parts too
+    let html = strip(&BOLD, &html, "*");
+    let html = ANCHOR
+        .replace_all(&html, |caps: &regex::Captures| {
+            let inner = strip(&TAG, &caps[2], "");
+            let inner = inner.split_whitespace().collect::<Vec<_>>().join(" ");
+            // placeholders so the tag stripping below leaves the url brackets alone
+            format!("{}\n\u{1}{}\u{2}\n", inner, &caps[1])
+        })
+        .into_owned();
+    let html = strip(&LINE_BREAK, &html, "\n");
+    let text = strip(&TAG, &html, "");
+
+    let text = text
+        .replace('\u{1}', "<")
+        .replace('\u{2}', ">")
+        .replace("&nbsp;", " ")
+        .replace("&middot;", "·")
+        .replace("&ndash;", "–")
+        .replace("&mdash;", "—")
+        .replace("&lsquo;", "‘")
+        .replace("&rsquo;", "’")
+        .replace("&ldquo;", "“")
+        .replace("&rdquo;", "”")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"");
+    // numeric references like &#183; and &#x2013;
+    let text = NUMERIC_REFERENCE
+        .replace_all(&text, |caps: &regex::Captures| {
+            let code = match (caps.name("dec"), caps.name("hex")) {
+                (Some(dec), _) => dec.as_str().parse().ok(),
+                (_, Some(hex)) => u32::from_str_radix(hex.as_str(), 16).ok(),
+                _ => None,
+            };
+            code.and_then(char::from_u32)
+                .map(String::from)
+                .unwrap_or_else(|| caps[0].to_string())
+        })
+        .into_owned();
+    // last, so an escaped `&amp;lt;` comes out as `&lt;` and not `<`
+    let text = text.replace("&amp;", "&");
+
+    // drop the indentation and blank line runs left behind by the markup
+    let mut lines: Vec<&str> = Vec::new();
+    for line in text.lines().map(str::trim) {
+        if line.is_empty() && lines.last().is_none_or(|last| last.is_empty()) {
+            continue;
+        }
+        lines.push(line);
+    }
+    lines.join("\n")
+}
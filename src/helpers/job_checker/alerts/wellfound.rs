@@ -0,0 +1,79 @@
+use super::{
+    as_link, flattened_html, job_blocks, links, non_empty_lines, search_term, AlertEmail, JobBlock,
+    JobTags, Site,
+};
+use crate::models::job::{JobAlertSource, ParsedJob};
+
+/// Wellfound digests group jobs under the startup posting them
+///
+/// ```text
+/// *Ferrous Labs*
+/// <https://wellfound.com/company/ferrous-labs?utm_source=job_digest>
+/// Memory-safe databases for the edge · 11-50 employees
+/// Software Engineer Intern
+/// <https://wellfound.com/jobs/2987654-software-engineer-intern?utm_source=job_digest>
+/// Internship · Remote · $30 – $40 / hr
+/// ```
+///
+/// so the employer is whichever company link came last before the job.
+pub fn parse(email: &AlertEmail) -> Vec<ParsedJob> {
+    let text = flattened_html(email);
+    let lines = non_empty_lines(&text);
+    let search_term = search_term(email);
+
+    // (index of the company link, company name), the logo links there too
+    let companies: Vec<(usize, &str)> = lines
+        .iter()
+        .enumerate()
+        .skip(1)
+        .filter(|(_, line)| {
+            as_link(line)
+                .and_then(links::canonicalize)
+                .is_some_and(|link| link.key.starts_with("url:wellfound.com/company/"))
+        })
+        .map(|(i, _)| (i, lines[i - 1].trim_matches('*').trim()))
+        .filter(|(_, name)| {
+            !name.is_empty() && !name.starts_with("[image:") && as_link(name).is_none()
+        })
+        .collect();
+
+    job_blocks(&lines, Site::Wellfound)
+        .iter()
+        .map(|block| {
+            let company = companies
+                .iter()
+                .rev()
+                .find(|(i, _)| *i < block.index)
+                .map(|(_, name)| name.to_string());
+            job_from_block(block, company, &search_term)
+        })
+        .collect()
+}
+
+fn job_from_block(
+    block: &JobBlock,
+    company: Option<String>,
+    search_term: &Option<String>,
+) -> ParsedJob {
+    // anything after the tags line is the role's pitch
+    let mut tags = JobTags::default();
+    if let Some(line) = block.details.first() {
+        tags.add(line);
+    }
+
+    ParsedJob {
+        source: JobAlertSource::Wellfound,
+        job_id: block.job_id.clone(),
+        key: block.link.key.clone(),
+        title: block.title.to_string(),
+        company,
+        location: tags.location.unwrap_or_default(),
+        workplace: tags.workplace,
+        salary: tags.salary,
+        job_type: tags.job_type,
+        deadline: None,
+        signals: Vec::new(),
+        search_term: search_term.clone(),
+        link: block.link.url.clone(),
+    }
+}
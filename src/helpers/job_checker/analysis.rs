@@ -0,0 +1,371 @@
+use std::{collections::BTreeMap, sync::LazyLock};
+
+use regex::{Captures, Regex};
+use serde::Deserialize;
+
+use super::alerts::html_to_text;
+use crate::models::posting::{JobAnalysis, PayPeriod, PayRange, WorkTerm};
+
+const MONTHS: &[&str] = &[
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+const MONTH_PATTERN: &str = r"jan(?:uary)?|feb(?:ruary)?|mar(?:ch)?|apr(?:il)?|may|june?|july?|aug(?:ust)?|sep(?:t(?:ember)?)?|oct(?:ober)?|nov(?:ember)?|dec(?:ember)?|winter|spring|summer|fall|autumn";
+
+const AMOUNT_PATTERN: &str = r"\d{1,3}(?:,\d{3})+(?:\.\d{1,2})?|\d+(?:\.\d{1,2})?";
+
+const CURRENCY_PATTERN: &str = r"cad|usd|eur|gbp|aud";
+
+/// What to look for in job descriptions.
+#[derive(Debug, Clone, Deserialize)]
+pub struct AnalysisConfig {
+    /// Skill name to the words that mean it, just the name when empty. Words
+    /// with capitals are matched with the same case, so `Go` doesn't match "go".
+    #[serde(default = "default_skills")]
+    pub skills: BTreeMap<String, Vec<String>>,
+}
+
+impl Default for AnalysisConfig {
+    fn default() -> Self {
+        Self {
+            skills: default_skills(),
+        }
+    }
+}
+
+fn default_skills() -> BTreeMap<String, Vec<String>> {
+    [
+        ("aws", &["aws", "amazon web services"][..]),
+        ("azure", &[]),
+        ("c#", &[]),
+        ("c++", &["c++", "cpp"]),
+        ("docker", &[]),
+        ("excel", &[]),
+        ("gcp", &["gcp", "google cloud"]),
+        ("git", &[]),
+        ("go", &["Go", "golang"]),
+        ("graphql", &[]),
+        ("java", &[]),
+        ("javascript", &["javascript", "JS"]),
+        ("kafka", &[]),
+        ("kotlin", &[]),
+        ("kubernetes", &["kubernetes", "k8s"]),
+        ("linux", &[]),
+        ("machine learning", &["machine learning", "ML"]),
+        ("node.js", &["node.js", "nodejs"]),
+        ("postgres", &["postgres", "postgresql"]),
+        ("python", &[]),
+        ("react", &[]),
+        ("ruby", &[]),
+        ("rust", &[]),
+        ("spark", &[]),
+        ("sql", &[]),
+        ("swift", &[]),
+        ("terraform", &[]),
+        ("typescript", &[]),
+    ]
+    .into_iter()
+    .map(|(name, aliases)| {
+        (
+            name.to_string(),
+            aliases.iter().map(|alias| alias.to_string()).collect(),
+        )
+    })
+    .collect()
+}
+
+/// Everything `JobAnalysis` covers that the description mentions.
+pub fn analyze(description: &str, config: &AnalysisConfig) -> JobAnalysis {
+    let text = plain_text(description);
+    JobAnalysis {
+        pay: pay(&text),
+        term: term(&text),
+        years_experience: years_experience(&text),
+        skills: skills(&text, config),
+    }
+}
+
+static HTML_TAG: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)</?[a-z][a-z0-9]*(\s[^<>]*)?/?>").unwrap());
+
+/// A description as plain text, whether it came as html, escaped html or
+/// plain text already.
+pub fn plain_text(description: &str) -> String {
+    let mut text = description.to_string();
+    // greenhouse escapes its markup, so it takes a second pass to come off
+    for _ in 0..2 {
+        if !HTML_TAG.is_match(&text) && !text.contains("&lt;") {
+            break;
+        }
+        text = html_to_text(&text);
+    }
+    text
+}
+
+static PAY: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(&format!(
+        r"(?i)(?:\b(?P<code>{currency})\s?[$€£]?|(?P<sign>[$€£]))\s?(?P<min>{amount})(?P<min_k>k\b)?(?:\s*(?:-|–|—|to)\s*(?:(?:{currency})\s?)?[$€£]?\s?(?P<max>{amount})(?P<max_k>k\b)?)?",
+        currency = CURRENCY_PATTERN,
+        amount = AMOUNT_PATTERN,
+    ))
+    .unwrap()
+});
+static PAY_PERIOD: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?i)^[^$€£]*?(?:(?:/\s*|\bper\s+|\ban?\s+)(?P<per>hour|hr|day|week|wk|month|mo|year|yr|annum)\b|\b(?P<adverb>hourly|daily|weekly|monthly|yearly|annually|annual)\b)",
+    )
+    .unwrap()
+});
+static TRAILING_CURRENCY: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(&format!(r"(?i)^[^$€£]{{0,12}}?\b({})\b", CURRENCY_PATTERN)).unwrap()
+});
+static FUNDING: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)^\s*(million|billion|[mb]\b)").unwrap());
+
+/// The first amount or range of money with a currency sign or code in front,
+/// e.g. `$28 - $34/hr` or `CAD 80,000–100,000 a year`. Without a period
+/// after it, small amounts are hourly and big ones yearly.
+fn pay(text: &str) -> Option<PayRange> {
+    for caps in PAY.captures_iter(text) {
+        // what comes right after, for the period and a currency code
+        let rest: String = text[caps.get(0).unwrap().end()..]
+            .chars()
+            .take_while(|c| *c != '\n')
+            .take(40)
+            .collect();
+        // funding rounds and revenue, not pay
+        if FUNDING.is_match(&rest) {
+            continue;
+        }
+
+        let thousands = caps.name("min_k").is_some() || caps.name("max_k").is_some();
+        let amount = |name: &str| {
+            caps.name(name).and_then(|amount| {
+                let amount: f64 = amount.as_str().replace(',', "").parse().ok()?;
+                Some(if thousands { amount * 1000.0 } else { amount })
+            })
+        };
+        let Some(min) = amount("min") else {
+            continue;
+        };
+        let max = amount("max").filter(|max| *max >= min).unwrap_or(min);
+
+        let period = match PAY_PERIOD.captures(&rest) {
+            Some(period) => {
+                let word = period
+                    .name("per")
+                    .or(period.name("adverb"))
+                    .unwrap()
+                    .as_str()
+                    .to_lowercase();
+                match word.as_str() {
+                    "hour" | "hr" | "hourly" => PayPeriod::Hour,
+                    "day" | "daily" => PayPeriod::Day,
+                    "week" | "wk" | "weekly" => PayPeriod::Week,
+                    "month" | "mo" | "monthly" => PayPeriod::Month,
+                    _ => PayPeriod::Year,
+                }
+            }
+            None if max < 200.0 => PayPeriod::Hour,
+            None if min >= 20_000.0 => PayPeriod::Year,
+            // could be a week or a month, or not pay at all
+            None => continue,
+        };
+
+        let currency = caps
+            .name("code")
+            .map(|code| code.as_str().to_uppercase())
+            .or_else(|| {
+                TRAILING_CURRENCY
+                    .captures(&rest)
+                    .map(|code| code[1].to_uppercase())
+            })
+            .or_else(|| match caps.name("sign").map(|sign| sign.as_str()) {
+                Some("€") => Some("EUR".to_string()),
+                Some("£") => Some("GBP".to_string()),
+                _ => None,
+            });
+
+        return Some(PayRange {
+            min,
+            max,
+            currency,
+            period,
+        });
+    }
+    None
+}
+
+static YEARS_EXPERIENCE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?i)\b(?P<years>\d{1,2})\s*\+?\s*(?:(?:-|–|to)\s*\d{1,2}\s*\+?\s*)?(?:years?|yrs?)\b(?:\s+of)?[^.\n]{0,40}?\bexperience",
+    )
+    .unwrap()
+});
+
+/// The fewest years of experience any line asks for, `3+ years of
+/// experience` or `1-2 years' professional experience`.
+fn years_experience(text: &str) -> Option<u32> {
+    YEARS_EXPERIENCE
+        .captures_iter(text)
+        .filter_map(|caps| caps["years"].parse().ok())
+        .min()
+}
+
+static ABOUT_TERM: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?i)\b(terms?|co-?op|intern(ship)?s?|placement|work period|duration|start(s|ing)?)\b",
+    )
+    .unwrap()
+});
+static TERM_MONTHS: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?i)\b(?P<months>\d{1,2}|four|six|eight|twelve|sixteen)(?:\s*(?:-|–|to|or)\s*\d{1,2})?[\s-]*months?\b",
+    )
+    .unwrap()
+});
+static TERM_DATES: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(&format!(
+        r"(?i)\b(?P<start>{months})\b\.?(?:\s+(?P<start_year>20\d{{2}}))?(?:\s*(?:-|–|to|through|until)\s*(?P<end>{months})\b\.?(?:\s+(?P<end_year>20\d{{2}}))?)?",
+        months = MONTH_PATTERN,
+    ))
+    .unwrap()
+});
+
+/// How long the term is and when it starts, only from sentences about the
+/// term so `within 12 months` elsewhere doesn't count.
+fn term(text: &str) -> Option<WorkTerm> {
+    let mut months = None;
+    let mut start = None;
+    for sentence in text.split(['\n', '.', ';']) {
+        if !ABOUT_TERM.is_match(sentence) {
+            continue;
+        }
+        if months.is_none() {
+            months = TERM_MONTHS
+                .captures(sentence)
+                .and_then(|caps| month_count(&caps["months"]));
+        }
+        // a later `June - August 2026` can still give the length
+        if start.is_none() || months.is_none() {
+            if let Some((when, length)) = TERM_DATES.captures_iter(sentence).find_map(term_dates) {
+                start = start.or(Some(when));
+                months = months.or(length);
+            }
+        }
+    }
+
+    (months.is_some() || start.is_some()).then_some(WorkTerm { months, start })
+}
+
+fn month_count(count: &str) -> Option<u32> {
+    match count.to_lowercase().as_str() {
+        "four" => Some(4),
+        "six" => Some(6),
+        "eight" => Some(8),
+        "twelve" => Some(12),
+        "sixteen" => Some(16),
+        count => count
+            .parse()
+            .ok()
+            .filter(|months| (1..=24).contains(months)),
+    }
+}
+
+/// `September 2025`, `Fall 2025` or the start of `January - August 2026`,
+/// with the length when both ends are months. Needs a year to count.
+fn term_dates(caps: Captures) -> Option<(String, Option<u32>)> {
+    let year = |name: &str| {
+        caps.name(name)
+            .and_then(|year| year.as_str().parse::<i32>().ok())
+    };
+    let (start_year, end_year) = (year("start_year"), year("end_year"));
+    let start = &caps["start"];
+    let end = caps.name("end").map(|end| end.as_str());
+
+    match (month_index(start), end.and_then(month_index)) {
+        (Some(start), Some(end)) => {
+            // December - April 2026 starts the year before
+            let wraps = i32::from(start > end);
+            let (start_year, end_year) = match (start_year, end_year) {
+                (Some(start_year), Some(end_year)) => (start_year, end_year),
+                (Some(start_year), None) => (start_year, start_year + wraps),
+                (None, Some(end_year)) => (end_year - wraps, end_year),
+                (None, None) => return None,
+            };
+            let length = (end_year * 12 + end as i32) - (start_year * 12 + start as i32) + 1;
+            Some((
+                format!("{} {}", MONTHS[start], start_year),
+                u32::try_from(length).ok().filter(|length| *length > 0),
+            ))
+        }
+        (start_month, _) => {
+            let start_year = start_year.or(end_year)?;
+            let name = match start_month {
+                Some(month) => MONTHS[month].to_string(),
+                None => season(start),
+            };
+            Some((format!("{} {}", name, start_year), None))
+        }
+    }
+}
+
+/// 0 for January, nothing for seasons.
+fn month_index(name: &str) -> Option<usize> {
+    let name = name.to_lowercase();
+    MONTHS
+        .iter()
+        .position(|month| month.to_lowercase().starts_with(&name[..3.min(name.len())]))
+        .filter(|_| !["winter", "spring", "summer", "fall", "autumn"].contains(&name.as_str()))
+}
+
+fn season(name: &str) -> String {
+    match name.to_lowercase().as_str() {
+        "autumn" | "fall" => "Fall".to_string(),
+        "winter" => "Winter".to_string(),
+        "spring" => "Spring".to_string(),
+        _ => "Summer".to_string(),
+    }
+}
+
+/// Dictionary skills the text mentions as whole words.
+fn skills(text: &str, config: &AnalysisConfig) -> Vec<String> {
+    let lower = text.to_lowercase();
+    config
+        .skills
+        .iter()
+        .filter(|(name, aliases)| {
+            if aliases.is_empty() {
+                mentions(text, &lower, name)
+            } else {
+                aliases.iter().any(|alias| mentions(text, &lower, alias))
+            }
+        })
+        .map(|(name, _)| name.clone())
+        .collect()
+}
+
+fn mentions(text: &str, lower: &str, word: &str) -> bool {
+    let (haystack, needle) = if word.chars().any(char::is_uppercase) {
+        (text, word.to_string())
+    } else {
+        (lower, word.to_lowercase())
+    };
+    haystack.match_indices(&needle).any(|(start, _)| {
+        let before = haystack[..start].chars().next_back();
+        let after = haystack[start + needle.len()..].chars().next();
+        // `java` isn't in `javascript`, and `c++` ends where the word does
+        !before.is_some_and(char::is_alphanumeric) && !after.is_some_and(char::is_alphanumeric)
+    })
+}
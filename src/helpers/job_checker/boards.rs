@@ -0,0 +1,215 @@
+use anyhow::{anyhow, Context};
+use reqwest::{Client, Url};
+use serde::Deserialize;
+use tracing::info;
+
+use crate::models::job::{greenhouse, lever, JobPosting};
+
+const GREENHOUSE_API: &str = "https://boards-api.greenhouse.io";
+const LEVER_API: &str = "https://api.lever.co";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BoardProvider {
+    Greenhouse,
+    Lever,
+}
+
+impl BoardProvider {
+    fn name(self) -> &'static str {
+        match self {
+            BoardProvider::Greenhouse => "greenhouse",
+            BoardProvider::Lever => "lever",
+        }
+    }
+}
+
+/// A company's public job board, `boards.greenhouse.io/<slug>` or
+/// `jobs.lever.co/<slug>`. Boards return every opening, so postings are only
+/// reported when they match the filters. They're all tracked though, so
+/// changing the filters doesn't look like jobs closing.
+#[derive(Debug, Clone, Deserialize)]
+pub struct JobBoardConfig {
+    pub name: String,
+    pub provider: BoardProvider,
+    /// The board token on Greenhouse, the company on Lever
+    pub slug: String,
+    /// Shown as the company on postings, defaults to the slug
+    pub company: Option<String>,
+    /// Titles have to contain one of these, any title when empty
+    #[serde(default)]
+    pub keywords: Vec<String>,
+    /// Locations have to contain one of these, anywhere when empty
+    #[serde(default)]
+    pub locations: Vec<String>,
+    /// Instead of the provider's public api, e.g. for a local stand-in
+    pub api_url: Option<String>,
+}
+
+impl JobBoardConfig {
+    pub fn validate(&self) -> Result<(), anyhow::Error> {
+        if let Some(api_url) = &self.api_url {
+            Url::parse(api_url).map_err(|e| {
+                anyhow!(
+                    "invalid api_url '{}' for job board '{}': {}",
+                    api_url,
+                    self.name,
+                    e
+                )
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Where postings from this board are tracked, see `job_checker::track_feed`
+    pub fn feed(&self) -> String {
+        format!("{}:{}", self.provider.name(), self.name)
+    }
+
+    fn api_url(&self) -> &str {
+        let default = match self.provider {
+            BoardProvider::Greenhouse => GREENHOUSE_API,
+            BoardProvider::Lever => LEVER_API,
+        };
+        self.api_url
+            .as_deref()
+            .unwrap_or(default)
+            .trim_end_matches('/')
+    }
+
+    fn company(&self) -> String {
+        self.company.clone().unwrap_or_else(|| self.slug.clone())
+    }
+
+    /// Whether a posting matches the keywords and locations
+    pub fn wanted(&self, posting: &JobPosting) -> bool {
+        let title = posting.title.to_lowercase();
+        let location = posting.location.to_lowercase();
+        (self.keywords.is_empty()
+            || self
+                .keywords
+                .iter()
+                .any(|keyword| title.contains(&keyword.to_lowercase())))
+            && (self.locations.is_empty()
+                || self
+                    .locations
+                    .iter()
+                    .any(|wanted| location.contains(&wanted.to_lowercase())))
+    }
+}
+
+/// Every posting on a board, `JobBoardConfig::wanted` picks the ones to report.
+pub async fn fetch(
+    client: &Client,
+    board: &JobBoardConfig,
+) -> Result<Vec<JobPosting>, anyhow::Error> {
+    info!("fetching {} board '{}'", board.provider.name(), board.name);
+    match board.provider {
+        BoardProvider::Greenhouse => greenhouse_postings(client, board).await,
+        BoardProvider::Lever => lever_postings(client, board).await,
+    }
+    .with_context(|| format!("unexpected response from job board '{}'", board.name))
+}
+
+async fn greenhouse_postings(
+    client: &Client,
+    board: &JobBoardConfig,
+) -> Result<Vec<JobPosting>, anyhow::Error> {
+    // content=true adds each job's description
+    let url = format!(
+        "{}/v1/boards/{}/jobs?content=true",
+        board.api_url(),
+        board.slug
+    );
+    let response: greenhouse::JobsResponse = client
+        .get(url)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    Ok(response
+        .jobs
+        .into_iter()
+        .map(|job| JobPosting {
+            source: "greenhouse".to_string(),
+            key: format!("greenhouse:{}", job.id),
+            title: job.title.trim().to_string(),
+            company: Some(board.company()),
+            location: job
+                .location
+                .map(|location| location.name.trim().to_string())
+                .unwrap_or_default(),
+            salary: None,
+            link: job.absolute_url,
+            description: job.content,
+            deadline: None,
+        })
+        .collect())
+}
+
+async fn lever_postings(
+    client: &Client,
+    board: &JobBoardConfig,
+) -> Result<Vec<JobPosting>, anyhow::Error> {
+    let url = format!("{}/v0/postings/{}?mode=json", board.api_url(), board.slug);
+    let response: Vec<lever::Posting> = client
+        .get(url)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    Ok(response
+        .into_iter()
+        .map(|posting| {
+            let mut location = posting.categories.location.unwrap_or_default();
+            if location.is_empty() && posting.workplace_type.as_deref() == Some("remote") {
+                location = "Remote".to_string();
+            }
+            JobPosting {
+                source: "lever".to_string(),
+                key: format!("lever:{}", posting.id),
+                title: posting.text.trim().to_string(),
+                company: Some(board.company()),
+                location: location.trim().to_string(),
+                salary: posting.salary_range.as_ref().and_then(lever_salary),
+                link: posting.hosted_url,
+                description: posting.description_plain,
+                deadline: None,
+            }
+        })
+        .collect())
+}
+
+/// `CAD 80,000–100,000 a year`, nothing for intervals we don't know.
+fn lever_salary(range: &lever::SalaryRange) -> Option<String> {
+    let interval = match range.interval.as_str() {
+        "per-year-salary" => "a year",
+        "per-month-salary" => "a month",
+        "per-week-salary" => "a week",
+        "per-day-wage" => "a day",
+        "per-hour-wage" => "an hour",
+        _ => return None,
+    };
+    let amount = if range.min == range.max {
+        thousands(range.min)
+    } else {
+        format!("{}–{}", thousands(range.min), thousands(range.max))
+    };
+    Some(format!("{} {} {}", range.currency, amount, interval))
+}
+
+fn thousands(amount: f64) -> String {
+    let digits = (amount.round() as u64).to_string();
+    let mut grouped = String::new();
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    grouped
+}
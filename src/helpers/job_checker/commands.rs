@@ -0,0 +1,134 @@
+use std::sync::LazyLock;
+
+use anyhow::anyhow;
+use mail_parser::{MessageParser, PartType};
+use regex::Regex;
+
+use super::alerts::html_to_text;
+use crate::models::{
+    posting::{ApplicationStatus, RuleKind},
+    reply::{ParsedReply, ReplyCommand},
+};
+
+/// Shown under every digest when replies are turned on.
+pub const HELP: &str = "Reply with one command per line, using the numbers above: \
+    save 3, applied 3, interviewing 3, rejected 3, offer 3, unsave 3, \
+    hide company Acme, hide title senior, unhide company Acme";
+
+/// Reads a reply to a digest. Only the lines above the quoted digest count.
+pub fn parse(raw: &[u8]) -> Result<ParsedReply, anyhow::Error> {
+    let message = MessageParser::default()
+        .parse(raw)
+        .ok_or_else(|| anyhow!("failed to parse reply ({} bytes)", raw.len()))?;
+
+    let text = message
+        .text_body
+        .iter()
+        .filter_map(|id| message.part(*id))
+        .find_map(|part| match &part.body {
+            PartType::Text(text) => Some(text.to_string()),
+            _ => None,
+        })
+        .or_else(|| message.body_html(0).map(|html| html_to_text(&html)))
+        .unwrap_or_default();
+
+    let mut reply = ParsedReply {
+        sender: message
+            .from()
+            .and_then(|from| from.first())
+            .and_then(|from| from.address())
+            .map(str::to_lowercase),
+        digest: message.subject().and_then(digest_id),
+        ..Default::default()
+    };
+
+    for line in new_lines(&text) {
+        match command(line) {
+            Some(command) => reply.commands.push(command),
+            None => reply.unknown.push(line.to_string()),
+        }
+    }
+    Ok(reply)
+}
+
+static DIGEST_ID: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?i)\[digest (\d+)\]").unwrap());
+
+/// `Re: Found 3 jobs [digest 42]` is digest 42.
+pub fn digest_id(subject: &str) -> Option<i64> {
+    DIGEST_ID
+        .captures(subject)
+        .and_then(|caps| caps[1].parse().ok())
+}
+
+static QUOTE_START: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?i)^(on .+ wrote:|-+\s*original message\s*-+|_{5,}|--|\*?from:\*? .+|sent from my .+)$",
+    )
+    .unwrap()
+});
+
+/// Lines written in the reply, stopping where the quoted digest or a
+/// signature starts. Outlook's bold `From:` header comes through as `*From:*`.
+fn new_lines(text: &str) -> Vec<&str> {
+    let mut lines = Vec::new();
+    for line in text.lines().map(str::trim) {
+        if QUOTE_START.is_match(line) {
+            break;
+        }
+        if line.is_empty() || line.starts_with('>') {
+            continue;
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+/// One line of a reply, nothing if it isn't a command.
+fn command(line: &str) -> Option<ReplyCommand> {
+    let (verb, rest) = line.split_once(char::is_whitespace)?;
+    let rest = rest.trim();
+
+    match verb.to_lowercase().as_str() {
+        "hide" | "unhide" => {
+            let (kind, value) = rest.split_once(char::is_whitespace)?;
+            let kind = match kind.to_lowercase().as_str() {
+                "company" => RuleKind::Company,
+                "title" => RuleKind::Title,
+                _ => return None,
+            };
+            let value = value.trim().to_string();
+            Some(if verb.eq_ignore_ascii_case("hide") {
+                ReplyCommand::Hide { kind, value }
+            } else {
+                ReplyCommand::Unhide { kind, value }
+            })
+        }
+        "unsave" => Some(ReplyCommand::Unsave {
+            codes: codes(rest)?,
+        }),
+        verb => {
+            let status = match verb {
+                "save" | "saved" => ApplicationStatus::Saved,
+                "apply" | "applied" => ApplicationStatus::Applied,
+                "interview" | "interviewing" => ApplicationStatus::Interviewing,
+                "reject" | "rejected" => ApplicationStatus::Rejected,
+                "offer" => ApplicationStatus::Offer,
+                _ => return None,
+            };
+            Some(ReplyCommand::Status {
+                status,
+                codes: codes(rest)?,
+            })
+        }
+    }
+}
+
+/// `3`, `3 5`, `#3, #5`, nothing unless every part is a number.
+fn codes(text: &str) -> Option<Vec<u32>> {
+    let codes: Option<Vec<u32>> = text
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|part| !part.is_empty())
+        .map(|part| part.trim_start_matches('#').parse().ok())
+        .collect();
+    codes.filter(|codes| !codes.is_empty())
+}
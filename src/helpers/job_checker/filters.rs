@@ -0,0 +1,57 @@
+use super::matching;
+use crate::{
+    config::FilterConfig,
+    models::{
+        job::JobPosting,
+        posting::{FilterRule, JobAnalysis, RuleKind},
+    },
+};
+
+/// Why a job's analysis rules it out, nothing when it's wanted.
+pub fn rejection(analysis: &JobAnalysis, filters: &FilterConfig) -> Option<String> {
+    if let (Some(max), Some(years)) = (filters.max_years_experience, analysis.years_experience) {
+        if years > max {
+            return Some(format!("asks for {} years of experience", years));
+        }
+    }
+
+    if let (Some(min), Some(pay)) = (filters.min_yearly_pay, &analysis.pay) {
+        let yearly = pay.max * pay.period.per_year();
+        if yearly < min {
+            return Some(format!("pays {:.0} a year at most", yearly));
+        }
+    }
+
+    let months = analysis.term.as_ref().and_then(|term| term.months);
+    if let (Some(max), Some(months)) = (filters.max_term_months, months) {
+        if months > max {
+            return Some(format!("{} month term", months));
+        }
+    }
+
+    // a description that names no skills could still be a fit
+    if !filters.skills.is_empty()
+        && !analysis.skills.is_empty()
+        && !analysis
+            .skills
+            .iter()
+            .any(|skill| filters.skills.contains(skill))
+    {
+        return Some(format!("only mentions {}", analysis.skills.join(", ")));
+    }
+
+    None
+}
+
+/// The first hide rule matching a posting.
+pub fn hiding_rule<'a>(posting: &JobPosting, rules: &'a [FilterRule]) -> Option<&'a FilterRule> {
+    let title = posting.title.to_lowercase();
+    let company = posting.company.as_deref().map(matching::company);
+    rules.iter().find(|rule| match rule.kind.as_str() {
+        kind if kind == RuleKind::Company.name() => {
+            company.as_deref() == Some(matching::company(&rule.value).as_str())
+        }
+        kind if kind == RuleKind::Title.name() => title.contains(&rule.value.to_lowercase()),
+        _ => false,
+    })
+}
@@ -0,0 +1,258 @@
+use std::sync::LazyLock;
+
+use regex::Regex;
+use reqwest::Url;
+
+/// How many redirect wrappers to unwrap before giving up on a link.
+const MAX_REDIRECTS: usize = 4;
+
+/// Query params redirect wrappers keep the real link in. Only followed when
+/// the value is itself a url, so a search for `?q=rust` is left alone.
+const REDIRECT_PARAMS: &[&str] = &[
+    "url",
+    "u",
+    "q",
+    "dest",
+    "destination",
+    "redirect",
+    "redirect_url",
+    "target",
+    "link",
+];
+
+/// Query params that only say where a click came from. Anything starting with
+/// `utm_` is dropped too.
+const TRACKING_PARAMS: &[&str] = &[
+    "trk",
+    "trkemail",
+    "trackingid",
+    "refid",
+    "lipi",
+    "midtoken",
+    "midsig",
+    "eid",
+    "otptoken",
+    "ref",
+    "ref_src",
+    "src",
+    "source",
+    "from",
+    "tk",
+    "qd",
+    "rd",
+    "alid",
+    "gclid",
+    "fbclid",
+    "msclkid",
+    "mc_cid",
+    "mc_eid",
+    "_hsenc",
+    "_hsmi",
+    "mkt_tok",
+];
+
+/// Job boards we know the posting urls of.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Site {
+    Linkedin,
+    Indeed,
+    Handshake,
+    Wellfound,
+    Glassdoor,
+}
+
+impl Site {
+    fn name(self) -> &'static str {
+        match self {
+            Site::Linkedin => "linkedin",
+            Site::Indeed => "indeed",
+            Site::Handshake => "handshake",
+            Site::Wellfound => "wellfound",
+            Site::Glassdoor => "glassdoor",
+        }
+    }
+
+    fn posting_url(self, job_id: &str) -> String {
+        match self {
+            Site::Linkedin => format!("https://www.linkedin.com/jobs/view/{}", job_id),
+            Site::Indeed => format!("https://www.indeed.com/viewjob?jk={}", job_id),
+            Site::Handshake => format!("https://app.joinhandshake.com/stu/jobs/{}", job_id),
+            Site::Wellfound => format!("https://wellfound.com/jobs/{}", job_id),
+            Site::Glassdoor => format!(
+                "https://www.glassdoor.com/job-listing/index.htm?jl={}",
+                job_id
+            ),
+        }
+    }
+}
+
+/// A job link with the click tracking taken out.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CanonicalLink {
+    /// `None` for pages we only know by url
+    pub site: Option<Site>,
+    pub job_id: Option<String>,
+    /// Same for every link to the posting, e.g. `linkedin:4241276548` or
+    /// `url:careers.example.com/jobs/123`
+    pub key: String,
+    /// Link to show people
+    pub url: String,
+}
+
+impl CanonicalLink {
+    /// A posting on a known job board, by its id there.
+    pub fn posting(site: Site, job_id: &str) -> Self {
+        Self {
+            site: Some(site),
+            job_id: Some(job_id.to_string()),
+            key: format!("{}:{}", site.name(), job_id),
+            url: site.posting_url(job_id),
+        }
+    }
+}
+
+/// Unwraps redirects and recognizes postings on known job boards, other links
+/// just lose their tracking params. All offline, nothing is fetched.
+pub fn canonicalize(link: &str) -> Option<CanonicalLink> {
+    let mut url = Url::parse(link.trim()).ok()?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return None;
+    }
+
+    for _ in 0..=MAX_REDIRECTS {
+        if let Some(posting) = known_posting(&url) {
+            return Some(posting);
+        }
+        match redirect_target(&url) {
+            Some(target) => url = target,
+            None => break,
+        }
+    }
+
+    Some(stripped(url))
+}
+
+fn on_domain(url: &Url, domain: &str) -> bool {
+    url.host_str()
+        .is_some_and(|host| host == domain || host.ends_with(&format!(".{}", domain)))
+}
+
+fn query_param(url: &Url, names: &[&str]) -> Option<String> {
+    url.query_pairs()
+        .find(|(name, _)| names.contains(&name.as_ref()))
+        .map(|(_, value)| value.into_owned())
+}
+
+/// `/comm/jobs/view/123` in emails, `/jobs/view/title-at-company-123` on the site
+static LINKEDIN_PATH: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^/(?:comm/)?jobs/view/(?:[^/]*-)?(?<id>\d+)").unwrap());
+static HANDSHAKE_PATH: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^/(?:stu/)?jobs/(?<id>\d+)").unwrap());
+static WELLFOUND_PATH: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^/(?:company/[^/]+/)?jobs/(?<id>\d+)").unwrap());
+/// Proofpoint v2's `-3A` style escapes
+static PROOFPOINT_ESCAPE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"-([0-9A-Fa-f]{2})").unwrap());
+
+fn path_id(url: &Url, pattern: &Regex) -> Option<String> {
+    pattern
+        .captures(url.path())
+        .map(|caps| caps["id"].to_string())
+}
+
+fn known_posting(url: &Url) -> Option<CanonicalLink> {
+    let (site, job_id) = if on_domain(url, "linkedin.com") {
+        let id = path_id(url, &LINKEDIN_PATH).or_else(|| query_param(url, &["currentJobId"]))?;
+        (Site::Linkedin, id)
+    } else if on_domain(url, "indeed.com") {
+        let key = query_param(url, &["jk", "vjk"])?.to_lowercase();
+        if key.len() != 16 || !key.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        (Site::Indeed, key)
+    } else if on_domain(url, "joinhandshake.com") {
+        (Site::Handshake, path_id(url, &HANDSHAKE_PATH)?)
+    } else if on_domain(url, "wellfound.com") || on_domain(url, "angel.co") {
+        (Site::Wellfound, path_id(url, &WELLFOUND_PATH)?)
+    } else if on_domain(url, "glassdoor.com") || on_domain(url, "glassdoor.ca") {
+        (Site::Glassdoor, query_param(url, &["jl", "jobListingId"])?)
+    } else {
+        return None;
+    };
+
+    job_id
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric())
+        .then(|| CanonicalLink::posting(site, &job_id))
+}
+
+/// Where a redirect wrapper points, if `url` is one.
+fn redirect_target(url: &Url) -> Option<Url> {
+    // proofpoint v3: https://urldefense.com/v3/__https://example.com/job__;!!abc$
+    if on_domain(url, "urldefense.com") {
+        let (_, wrapped) = url.as_str().split_once("/v3/__")?;
+        let target = wrapped.split("__;").next()?;
+        return web_url(target);
+    }
+    // proofpoint v2 encodes the link as https-3A__example.com_job
+    if on_domain(url, "urldefense.proofpoint.com") {
+        let wrapped = query_param(url, &["u"])?.replace('_', "/");
+        let target = PROOFPOINT_ESCAPE
+            .replace_all(&wrapped, |caps: &regex::Captures| {
+                u8::from_str_radix(&caps[1], 16)
+                    .map(|byte| (byte as char).to_string())
+                    .unwrap_or_else(|_| caps[0].to_string())
+            })
+            .into_owned();
+        return web_url(&target);
+    }
+
+    url.query_pairs()
+        .filter(|(name, _)| REDIRECT_PARAMS.contains(&name.as_ref()))
+        .find_map(|(_, value)| web_url(&value))
+}
+
+fn web_url(link: &str) -> Option<Url> {
+    Url::parse(link)
+        .ok()
+        .filter(|url| matches!(url.scheme(), "http" | "https"))
+}
+
+/// Drops the fragment and tracking params and sorts what's left, so links that
+/// only differ in where they were clicked from get the same key.
+fn stripped(mut url: Url) -> CanonicalLink {
+    let mut params: Vec<(String, String)> = url
+        .query_pairs()
+        .filter(|(name, _)| {
+            let name = name.to_lowercase();
+            !name.starts_with("utm_") && !TRACKING_PARAMS.contains(&name.as_str())
+        })
+        .map(|(name, value)| (name.into_owned(), value.into_owned()))
+        .collect();
+    params.sort();
+
+    url.set_fragment(None);
+    if params.is_empty() {
+        url.set_query(None);
+    } else {
+        url.query_pairs_mut().clear().extend_pairs(&params);
+    }
+
+    let host = url.host_str().unwrap_or_default();
+    let host = host.strip_prefix("www.").unwrap_or(host);
+    let path = match url.path().trim_end_matches('/') {
+        "" => "/",
+        path => path,
+    };
+    let key = match url.query() {
+        Some(query) => format!("url:{}{}?{}", host, path, query),
+        None => format!("url:{}{}", host, path),
+    };
+
+    CanonicalLink {
+        site: None,
+        job_id: None,
+        key,
+        url: url.to_string(),
+    }
+}
@@ -0,0 +1,147 @@
+use strsim::sorensen_dice;
+
+use crate::{
+    config::MatchingConfig,
+    models::job::{JobPosting, MergedJob},
+};
+
+/// Words that don't tell companies apart, `Acme Corp.` is `Acme Corporation`.
+const COMPANY_SUFFIXES: &[&str] = &[
+    "inc",
+    "incorporated",
+    "ltd",
+    "limited",
+    "llc",
+    "llp",
+    "corp",
+    "corporation",
+    "co",
+    "company",
+    "plc",
+    "gmbh",
+    "the",
+];
+
+/// Whether two postings look like the same job. Postings without a company
+/// only match by key, a title alone is too generic.
+pub fn is_same_job(a: &JobPosting, b: &JobPosting, config: &MatchingConfig) -> bool {
+    if a.key == b.key {
+        return true;
+    }
+    let (Some(company_a), Some(company_b)) = (&a.company, &b.company) else {
+        return false;
+    };
+
+    similarity(&company(company_a), &company(company_b)) >= config.company_threshold
+        && similarity(&title(&a.title), &title(&b.title)) >= config.title_threshold
+        && same_place(&a.location, &b.location, config)
+}
+
+/// Groups postings of the same job, a posting joins the first group it
+/// matches any member of. Groups keep the order postings came in.
+pub fn cluster(postings: Vec<JobPosting>, config: &MatchingConfig) -> Vec<Vec<JobPosting>> {
+    let mut groups: Vec<Vec<JobPosting>> = Vec::new();
+    for posting in postings {
+        match groups.iter_mut().find(|group| {
+            group
+                .iter()
+                .any(|other| is_same_job(&posting, other, config))
+        }) {
+            Some(group) => group.push(posting),
+            None => groups.push(vec![posting]),
+        }
+    }
+    groups
+}
+
+/// One job out of a group from `cluster`, with a posting per distinct link.
+/// Details come from the first posting, gaps are filled in from the others.
+pub fn merge(group: Vec<JobPosting>) -> MergedJob {
+    let mut postings: Vec<JobPosting> = Vec::new();
+    for posting in group {
+        if !postings.iter().any(|other| other.key == posting.key) {
+            postings.push(posting);
+        }
+    }
+
+    let first = &postings[0];
+    MergedJob {
+        title: first.title.clone(),
+        company: postings.iter().find_map(|p| p.company.clone()),
+        location: postings
+            .iter()
+            .map(|p| p.location.clone())
+            .find(|location| !location.is_empty())
+            .unwrap_or_default(),
+        salary: postings.iter().find_map(|p| p.salary.clone()),
+        postings,
+    }
+}
+
+fn similarity(a: &str, b: &str) -> f64 {
+    if a == b {
+        1.0
+    } else {
+        sorensen_dice(a, b)
+    }
+}
+
+/// Lowercase words, punctuation dropped.
+fn words(text: &str) -> Vec<String> {
+    text.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_string())
+        .collect()
+}
+
+/// A company name without suffixes, for comparing.
+pub(super) fn company(name: &str) -> String {
+    let words = words(name);
+    let kept: Vec<&str> = words
+        .iter()
+        .map(|word| word.as_str())
+        .filter(|word| !COMPANY_SUFFIXES.contains(word))
+        .collect();
+    // a company called just `The Company` keeps its name
+    if kept.is_empty() {
+        words.join(" ")
+    } else {
+        kept.join(" ")
+    }
+}
+
+/// `Software Developer Co-op` and `Software Developer (Coop)` are the same,
+/// so are `Intern` and `Internship`.
+fn title(title: &str) -> String {
+    let mut normalized: Vec<String> = Vec::new();
+    for word in words(title) {
+        if word == "op" && normalized.last().is_some_and(|last| last == "co") {
+            normalized.pop();
+            normalized.push("coop".to_string());
+            continue;
+        }
+        normalized.push(match word.as_str() {
+            "internship" => "intern".to_string(),
+            "sr" => "senior".to_string(),
+            "jr" => "junior".to_string(),
+            _ => word,
+        });
+    }
+    normalized.join(" ")
+}
+
+/// Compares cities, sites disagree on how to write the rest
+/// (`Toronto, ON` vs `Toronto, Ontario, Canada`). Remote or missing
+/// locations don't rule anything out.
+fn same_place(a: &str, b: &str, config: &MatchingConfig) -> bool {
+    let (a, b) = (city(a), city(b));
+    if a.is_empty() || b.is_empty() || a == "remote" || b == "remote" {
+        return true;
+    }
+    similarity(&a, &b) >= config.location_threshold
+}
+
+fn city(location: &str) -> String {
+    words(location.split(',').next().unwrap_or_default()).join(" ")
+}
@@ -0,0 +1,178 @@
+use std::{collections::BTreeMap, sync::LazyLock};
+
+use anyhow::{anyhow, Context};
+use regex::Regex;
+use reqwest::{Client, Url};
+use serde::Deserialize;
+use tracing::info;
+
+use super::links;
+use crate::models::job::{workday, JobPosting};
+
+/// Workday won't return more than this many jobs per page.
+const MAX_PAGE_SIZE: u32 = 20;
+
+/// A company's Workday career site, e.g.
+/// `https://optum.wd5.myworkdayjobs.com/External` is base url
+/// `https://optum.wd5.myworkdayjobs.com`, tenant `optum` and site `External`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct WorkdayConfig {
+    pub name: String,
+    pub base_url: String,
+    pub tenant: String,
+    pub site: String,
+    /// Shown as the company on postings, defaults to the tenant
+    pub company: Option<String>,
+    #[serde(default)]
+    pub search_text: String,
+    /// Facet ids to filter on, copied from the `appliedFacets` of a search on the site
+    #[serde(default)]
+    pub facets: BTreeMap<String, Vec<String>>,
+    #[serde(default = "default_page_size")]
+    pub page_size: u32,
+    #[serde(default = "default_max_pages")]
+    pub max_pages: u32,
+}
+
+impl WorkdayConfig {
+    pub fn validate(&self) -> Result<(), anyhow::Error> {
+        Url::parse(&self.base_url).map_err(|e| {
+            anyhow!(
+                "invalid base_url '{}' for workday site '{}': {}",
+                self.base_url,
+                self.name,
+                e
+            )
+        })?;
+        if !(1..=MAX_PAGE_SIZE).contains(&self.page_size) {
+            return Err(anyhow!(
+                "page_size for workday site '{}' must be between 1 and {}",
+                self.name,
+                MAX_PAGE_SIZE
+            ));
+        }
+        Ok(())
+    }
+
+    /// Where postings from this site are tracked, see `job_checker::track_feed`
+    pub fn feed(&self) -> String {
+        format!("workday:{}", self.name)
+    }
+
+    /// Whether `fetched` postings could be all of them, rather than the search
+    /// running into `max_pages`.
+    pub fn got_everything(&self, fetched: usize) -> bool {
+        fetched < (self.max_pages * self.page_size) as usize
+    }
+
+    fn jobs_url(&self) -> String {
+        format!(
+            "{}/wday/cxs/{}/{}/jobs",
+            self.base_url.trim_end_matches('/'),
+            self.tenant,
+            self.site
+        )
+    }
+
+    fn posting_url(&self, external_path: &str) -> String {
+        format!(
+            "{}/{}{}",
+            self.base_url.trim_end_matches('/'),
+            self.site,
+            external_path
+        )
+    }
+}
+
+fn default_page_size() -> u32 {
+    MAX_PAGE_SIZE
+}
+
+fn default_max_pages() -> u32 {
+    5
+}
+
+/// Pages through a site's search results, stopping at `max_pages`.
+pub async fn fetch(
+    client: &Client,
+    config: &WorkdayConfig,
+) -> Result<Vec<JobPosting>, anyhow::Error> {
+    let mut postings = Vec::new();
+    let mut total = None;
+
+    for page in 0..config.max_pages {
+        let offset = page * config.page_size;
+        info!(
+            "fetching workday site '{}' at offset {}",
+            config.name, offset
+        );
+
+        let request = workday::JobsRequest {
+            applied_facets: config.facets.clone(),
+            limit: config.page_size,
+            offset,
+            search_text: config.search_text.clone(),
+        };
+        let response: workday::JobsResponse = client
+            .post(config.jobs_url())
+            .json(&request)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await
+            .with_context(|| format!("unexpected response from workday site '{}'", config.name))?;
+
+        let total = *total.get_or_insert(response.total);
+        let found = response.job_postings.len() as u32;
+        postings.extend(response.job_postings.iter().map(|job| posting(config, job)));
+
+        // a full page with no total might not be the last one
+        if found < config.page_size || (total > 0 && offset + found >= total) {
+            break;
+        }
+    }
+
+    info!(
+        "found {} jobs on workday site '{}'",
+        postings.len(),
+        config.name
+    );
+    Ok(postings)
+}
+
+/// `3 Locations` doesn't say which
+static SEVERAL_LOCATIONS: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)^\d+ locations$").unwrap());
+
+fn posting(config: &WorkdayConfig, job: &workday::Job) -> JobPosting {
+    let link = config.posting_url(&job.external_path);
+    let key = links::canonicalize(&link)
+        .map(|link| link.key)
+        .unwrap_or_else(|| format!("workday:{}:{}", config.tenant, job.external_path));
+
+    // an empty location matches any
+    let location = if SEVERAL_LOCATIONS.is_match(job.locations_text.trim()) {
+        String::new()
+    } else {
+        job.locations_text.trim().to_string()
+    };
+
+    JobPosting {
+        source: "workday".to_string(),
+        key,
+        title: job.title.trim().to_string(),
+        company: Some(
+            config
+                .company
+                .clone()
+                .unwrap_or_else(|| config.tenant.clone()),
+        ),
+        location,
+        salary: None,
+        link,
+        // only on the posting's own page, which would be a request per job
+        description: None,
+        deadline: None,
+    }
+}
@@ -0,0 +1,528 @@
+use std::collections::HashSet;
+
+use chrono::{DateTime, NaiveDate, Utc};
+use sqlx::{Sqlite, SqlitePool, Transaction};
+
+use crate::models::{
+    job::JobPosting,
+    posting::{
+        ApplicationEvent, ApplicationStatus, FilterRule, JobAnalysis, PostingRevision, RuleKind,
+        StoredPosting,
+    },
+};
+
+const SELECT_POSTING: &str =
+    "SELECT key, source, title, company, location, salary, link, description,
+    deadline, feed, first_seen_at, last_seen_at, closed_at, saved_at, status, status_changed_at,
+    notion_page_id, pay_min, pay_max, pay_currency, pay_period, term_months, term_start,
+    years_experience, skills FROM job_postings";
+
+/// Saves postings, ones seen before get their details and last seen time
+/// updated and a revision for every field that changed. `feed` is set for
+/// postings polled from a careers site or board, so they can be closed when
+/// they stop showing up there. Returns the keys of postings that weren't
+/// stored yet or had closed, and the revisions.
+pub async fn record(
+    pool: &SqlitePool,
+    postings: &[JobPosting],
+    feed: Option<&str>,
+) -> Result<(Vec<String>, Vec<PostingRevision>), anyhow::Error> {
+    let now = Utc::now().to_rfc3339();
+    let mut new = Vec::new();
+    let mut revisions = Vec::new();
+    let mut tx = pool.begin().await?;
+    for posting in postings {
+        let stored: Option<StoredPosting> =
+            sqlx::query_as(&format!("{} WHERE key = ?", SELECT_POSTING))
+                .bind(&posting.key)
+                .fetch_optional(&mut *tx)
+                .await?;
+        match &stored {
+            Some(stored) => {
+                if stored.closed_at.is_some() {
+                    new.push(posting.key.clone());
+                }
+                for (field, old_value, new_value) in changes(stored, posting) {
+                    revisions.push(
+                        add_revision(&mut tx, &posting.key, field, old_value, new_value, &now)
+                            .await?,
+                    );
+                }
+            }
+            None => new.push(posting.key.clone()),
+        }
+
+        // details missing from this sighting, like the salary an alert left
+        // out, don't wipe out what we already know
+        sqlx::query(
+            "INSERT INTO job_postings
+                (key, source, title, company, location, salary, link, description, deadline,
+                 feed, first_seen_at, last_seen_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+             ON CONFLICT(key) DO UPDATE SET
+                source = excluded.source,
+                title = excluded.title,
+                company = COALESCE(excluded.company, company),
+                location = CASE WHEN excluded.location = '' THEN location ELSE excluded.location END,
+                salary = COALESCE(excluded.salary, salary),
+                link = excluded.link,
+                description = COALESCE(excluded.description, description),
+                deadline = COALESCE(excluded.deadline, deadline),
+                feed = COALESCE(excluded.feed, feed),
+                last_seen_at = excluded.last_seen_at,
+                closed_at = NULL,
+                closing_notified_at = CASE WHEN excluded.deadline IS NOT NULL
+                    AND excluded.deadline IS NOT deadline THEN NULL ELSE closing_notified_at END",
+        )
+        .bind(&posting.key)
+        .bind(&posting.source)
+        .bind(&posting.title)
+        .bind(&posting.company)
+        .bind(&posting.location)
+        .bind(&posting.salary)
+        .bind(&posting.link)
+        .bind(&posting.description)
+        .bind(&posting.deadline)
+        .bind(feed)
+        .bind(&now)
+        .bind(&now)
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await?;
+    Ok((new, revisions))
+}
+
+/// What's different about a posting since it was stored, as (field, old, new).
+/// Empty fields in the new sighting don't count as changes.
+fn changes(
+    stored: &StoredPosting,
+    posting: &JobPosting,
+) -> Vec<(&'static str, Option<String>, Option<String>)> {
+    let mut changes = Vec::new();
+    let mut compare = |field, old: &Option<String>, new: &Option<String>| {
+        if new.as_deref().is_some_and(|new| !new.is_empty()) && old != new {
+            changes.push((field, old.clone(), new.clone()));
+        }
+    };
+    compare(
+        "title",
+        &Some(stored.title.clone()),
+        &Some(posting.title.clone()),
+    );
+    compare(
+        "location",
+        &Some(stored.location.clone()),
+        &Some(posting.location.clone()),
+    );
+    compare("salary", &stored.salary, &posting.salary);
+    compare("description", &stored.description, &posting.description);
+    compare("deadline", &stored.deadline, &posting.deadline);
+    if stored.closed_at.is_some() {
+        changes.push((
+            "status",
+            Some("closed".to_string()),
+            Some("open".to_string()),
+        ));
+    }
+    changes
+}
+
+async fn add_revision(
+    tx: &mut Transaction<'_, Sqlite>,
+    key: &str,
+    field: &str,
+    old_value: Option<String>,
+    new_value: Option<String>,
+    changed_at: &str,
+) -> Result<PostingRevision, anyhow::Error> {
+    let result = sqlx::query(
+        "INSERT INTO job_revisions (key, field, old_value, new_value, changed_at)
+         VALUES (?, ?, ?, ?, ?)",
+    )
+    .bind(key)
+    .bind(field)
+    .bind(&old_value)
+    .bind(&new_value)
+    .bind(changed_at)
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(PostingRevision {
+        id: result.last_insert_rowid(),
+        key: key.to_string(),
+        field: field.to_string(),
+        old_value,
+        new_value,
+        changed_at: changed_at.to_string(),
+    })
+}
+
+/// Closes the open postings from `feed` that weren't in its latest poll,
+/// returning a revision for each.
+pub async fn close_missing(
+    pool: &SqlitePool,
+    feed: &str,
+    seen: &[JobPosting],
+) -> Result<Vec<PostingRevision>, anyhow::Error> {
+    let seen: HashSet<&str> = seen.iter().map(|posting| posting.key.as_str()).collect();
+    let open: Vec<String> =
+        sqlx::query_scalar("SELECT key FROM job_postings WHERE feed = ? AND closed_at IS NULL")
+            .bind(feed)
+            .fetch_all(pool)
+            .await?;
+
+    let now = Utc::now().to_rfc3339();
+    let mut closed = Vec::new();
+    let mut tx = pool.begin().await?;
+    for key in open.iter().filter(|key| !seen.contains(key.as_str())) {
+        sqlx::query("UPDATE job_postings SET closed_at = ? WHERE key = ?")
+            .bind(&now)
+            .bind(key)
+            .execute(&mut *tx)
+            .await?;
+        closed.push(
+            add_revision(
+                &mut tx,
+                key,
+                "status",
+                Some("open".to_string()),
+                Some("closed".to_string()),
+                &now,
+            )
+            .await?,
+        );
+    }
+    tx.commit().await?;
+    Ok(closed)
+}
+
+/// Stores what was pulled out of a posting's description, replacing the last
+/// analysis. Does nothing for postings that weren't recorded.
+pub async fn save_analysis(
+    pool: &SqlitePool,
+    key: &str,
+    analysis: &JobAnalysis,
+) -> Result<(), anyhow::Error> {
+    let pay = analysis.pay.as_ref();
+    let term = analysis.term.as_ref();
+    sqlx::query(
+        "UPDATE job_postings SET pay_min = ?, pay_max = ?, pay_currency = ?, pay_period = ?,
+            term_months = ?, term_start = ?, years_experience = ?, skills = ?
+         WHERE key = ?",
+    )
+    .bind(pay.map(|pay| pay.min))
+    .bind(pay.map(|pay| pay.max))
+    .bind(pay.and_then(|pay| pay.currency.as_deref()))
+    .bind(pay.map(|pay| pay.period.name()))
+    .bind(term.and_then(|term| term.months))
+    .bind(term.and_then(|term| term.start.as_deref()))
+    .bind(analysis.years_experience)
+    .bind((!analysis.skills.is_empty()).then(|| analysis.skills.join(",")))
+    .bind(key)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Open postings seen at or after `since`, most recent first.
+pub async fn seen_since(
+    pool: &SqlitePool,
+    since: DateTime<Utc>,
+) -> Result<Vec<JobPosting>, anyhow::Error> {
+    let postings = sqlx::query_as(
+        "SELECT key, source, title, company, location, salary, link, description, deadline
+         FROM job_postings WHERE last_seen_at >= ? AND closed_at IS NULL
+         ORDER BY last_seen_at DESC",
+    )
+    .bind(since.to_rfc3339())
+    .fetch_all(pool)
+    .await?;
+    Ok(postings)
+}
+
+pub async fn get(pool: &SqlitePool, key: &str) -> Result<Option<StoredPosting>, anyhow::Error> {
+    let posting = sqlx::query_as(&format!("{} WHERE key = ?", SELECT_POSTING))
+        .bind(key)
+        .fetch_optional(pool)
+        .await?;
+    Ok(posting)
+}
+
+/// A posting's revisions, oldest first.
+pub async fn revisions(
+    pool: &SqlitePool,
+    key: &str,
+) -> Result<Vec<PostingRevision>, anyhow::Error> {
+    let revisions = sqlx::query_as(
+        "SELECT id, key, field, old_value, new_value, changed_at FROM job_revisions
+         WHERE key = ? ORDER BY id",
+    )
+    .bind(key)
+    .fetch_all(pool)
+    .await?;
+    Ok(revisions)
+}
+
+/// Takes a posting out of the pipeline, it stops getting update emails but
+/// keeps its history. False if there's no posting with that key.
+pub async fn unsave(pool: &SqlitePool, key: &str) -> Result<bool, anyhow::Error> {
+    let result = sqlx::query(
+        "UPDATE job_postings SET saved_at = NULL, status = NULL, status_changed_at = NULL
+         WHERE key = ?",
+    )
+    .bind(key)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Moves a posting to `status`, saving it if it wasn't, and records the step
+/// with its note and the day it happened. Nothing if there's no posting with
+/// that key.
+pub async fn set_status(
+    pool: &SqlitePool,
+    key: &str,
+    status: ApplicationStatus,
+    note: Option<&str>,
+    date: NaiveDate,
+) -> Result<Option<ApplicationEvent>, anyhow::Error> {
+    let now = Utc::now().to_rfc3339();
+    let mut tx = pool.begin().await?;
+    let result = sqlx::query(
+        "UPDATE job_postings SET saved_at = COALESCE(saved_at, ?), status = ?,
+            status_changed_at = ?, stale_notified_at = NULL
+         WHERE key = ?",
+    )
+    .bind(&now)
+    .bind(status.name())
+    .bind(&now)
+    .bind(key)
+    .execute(&mut *tx)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Ok(None);
+    }
+
+    let event = sqlx::query_as(
+        "INSERT INTO application_events (key, status, note, date, created_at)
+         VALUES (?, ?, ?, ?, ?)
+         RETURNING id, key, status, note, date, created_at",
+    )
+    .bind(key)
+    .bind(status.name())
+    .bind(note)
+    .bind(date.to_string())
+    .bind(&now)
+    .fetch_one(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    Ok(Some(event))
+}
+
+/// A posting's application steps, oldest first.
+pub async fn events(pool: &SqlitePool, key: &str) -> Result<Vec<ApplicationEvent>, anyhow::Error> {
+    let events = sqlx::query_as(
+        "SELECT id, key, status, note, date, created_at FROM application_events
+         WHERE key = ? ORDER BY id",
+    )
+    .bind(key)
+    .fetch_all(pool)
+    .await?;
+    Ok(events)
+}
+
+/// Postings in the pipeline, only ones at `status` when given, most recently
+/// moved first.
+pub async fn by_status(
+    pool: &SqlitePool,
+    status: Option<ApplicationStatus>,
+) -> Result<Vec<StoredPosting>, anyhow::Error> {
+    let postings = sqlx::query_as(&format!(
+        "{} WHERE status IS NOT NULL AND (? IS NULL OR status = ?)
+         ORDER BY status_changed_at DESC",
+        SELECT_POSTING
+    ))
+    .bind(status.map(ApplicationStatus::name))
+    .bind(status.map(ApplicationStatus::name))
+    .fetch_all(pool)
+    .await?;
+    Ok(postings)
+}
+
+/// Applications still waiting to hear back that haven't moved since `before`,
+/// leaving out ones already reminded about.
+pub async fn stale_applications(
+    pool: &SqlitePool,
+    before: DateTime<Utc>,
+) -> Result<Vec<StoredPosting>, anyhow::Error> {
+    let pending: Vec<String> = ApplicationStatus::ALL
+        .into_iter()
+        .filter(|status| status.is_pending())
+        .map(|status| format!("'{}'", status.name()))
+        .collect();
+    let postings = sqlx::query_as(&format!(
+        "{} WHERE status IN ({}) AND status_changed_at < ? AND stale_notified_at IS NULL
+         ORDER BY status_changed_at",
+        SELECT_POSTING,
+        pending.join(", ")
+    ))
+    .bind(before.to_rfc3339())
+    .fetch_all(pool)
+    .await?;
+    Ok(postings)
+}
+
+pub async fn mark_stale_notified(
+    pool: &SqlitePool,
+    postings: &[StoredPosting],
+) -> Result<(), anyhow::Error> {
+    let now = Utc::now().to_rfc3339();
+    for posting in postings {
+        sqlx::query("UPDATE job_postings SET stale_notified_at = ? WHERE key = ?")
+            .bind(&now)
+            .bind(&posting.key)
+            .execute(pool)
+            .await?;
+    }
+    Ok(())
+}
+
+pub async fn set_notion_page(
+    pool: &SqlitePool,
+    key: &str,
+    page_id: &str,
+) -> Result<(), anyhow::Error> {
+    sqlx::query("UPDATE job_postings SET notion_page_id = ? WHERE key = ?")
+        .bind(page_id)
+        .bind(key)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Open saved postings with a deadline on or before `by` that nobody was
+/// reminded about yet. Ones already applied to don't need the deadline.
+pub async fn saved_closing_by(
+    pool: &SqlitePool,
+    by: NaiveDate,
+) -> Result<Vec<StoredPosting>, anyhow::Error> {
+    let postings = sqlx::query_as(&format!(
+        "{} WHERE saved_at IS NOT NULL AND (status IS NULL OR status = 'saved')
+         AND closed_at IS NULL AND deadline <= ? AND closing_notified_at IS NULL
+         ORDER BY deadline",
+        SELECT_POSTING
+    ))
+    .bind(by.to_string())
+    .fetch_all(pool)
+    .await?;
+    Ok(postings)
+}
+
+pub async fn mark_closing_notified(
+    pool: &SqlitePool,
+    postings: &[StoredPosting],
+) -> Result<(), anyhow::Error> {
+    let now = Utc::now().to_rfc3339();
+    for posting in postings {
+        sqlx::query("UPDATE job_postings SET closing_notified_at = ? WHERE key = ?")
+            .bind(&now)
+            .bind(&posting.key)
+            .execute(pool)
+            .await?;
+    }
+    Ok(())
+}
+
+/// Records a digest about to be sent, numbering its jobs from 1 in the order
+/// given, and returns its id.
+pub async fn new_digest(
+    pool: &SqlitePool,
+    subject: &str,
+    token: &str,
+    keys: &[&str],
+) -> Result<i64, anyhow::Error> {
+    let mut tx = pool.begin().await?;
+    let digest = sqlx::query("INSERT INTO job_digests (subject, sent_at, token) VALUES (?, ?, ?)")
+        .bind(subject)
+        .bind(Utc::now().to_rfc3339())
+        .bind(token)
+        .execute(&mut *tx)
+        .await?
+        .last_insert_rowid();
+    for (i, key) in keys.iter().enumerate() {
+        sqlx::query("INSERT INTO digest_jobs (digest_id, code, key) VALUES (?, ?, ?)")
+            .bind(digest)
+            .bind(i as i64 + 1)
+            .bind(key)
+            .execute(&mut *tx)
+            .await?;
+    }
+    tx.commit().await?;
+    Ok(digest)
+}
+
+/// The digest that went out with `token` in its Reply-To.
+pub async fn digest_for_token(
+    pool: &SqlitePool,
+    token: &str,
+) -> Result<Option<i64>, anyhow::Error> {
+    let digest = sqlx::query_scalar("SELECT id FROM job_digests WHERE token = ?")
+        .bind(token)
+        .fetch_optional(pool)
+        .await?;
+    Ok(digest)
+}
+
+/// The posting numbered `code` in a digest.
+pub async fn digest_key(
+    pool: &SqlitePool,
+    digest: i64,
+    code: u32,
+) -> Result<Option<String>, anyhow::Error> {
+    let key = sqlx::query_scalar("SELECT key FROM digest_jobs WHERE digest_id = ? AND code = ?")
+        .bind(digest)
+        .bind(code)
+        .fetch_optional(pool)
+        .await?;
+    Ok(key)
+}
+
+pub async fn rules(pool: &SqlitePool) -> Result<Vec<FilterRule>, anyhow::Error> {
+    let rules = sqlx::query_as("SELECT id, kind, value, created_at FROM filter_rules ORDER BY id")
+        .fetch_all(pool)
+        .await?;
+    Ok(rules)
+}
+
+/// False if there already was the same rule, in any case.
+pub async fn add_rule(
+    pool: &SqlitePool,
+    kind: RuleKind,
+    value: &str,
+) -> Result<bool, anyhow::Error> {
+    let result = sqlx::query(
+        "INSERT INTO filter_rules (kind, value, created_at) VALUES (?, ?, ?)
+         ON CONFLICT (kind, value) DO NOTHING",
+    )
+    .bind(kind.name())
+    .bind(value)
+    .bind(Utc::now().to_rfc3339())
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// False if there was no such rule, values are compared case-insensitively.
+pub async fn remove_rule(
+    pool: &SqlitePool,
+    kind: RuleKind,
+    value: &str,
+) -> Result<bool, anyhow::Error> {
+    let result = sqlx::query("DELETE FROM filter_rules WHERE kind = ? AND value = ?")
+        .bind(kind.name())
+        .bind(value)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
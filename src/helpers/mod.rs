@@ -1,3 +1,10 @@
+pub mod archive;
+pub mod cron_history;
+pub mod db;
 pub mod email;
+pub mod error_reporter;
+pub mod imap;
 pub mod notion;
-pub mod job_checker;
\ No newline at end of file
+pub mod job_checker;
+pub mod job_store;
+pub mod processed_emails;
@@ -1,9 +1,18 @@
+use anyhow::anyhow;
 use reqwest::{header, Client};
+use serde_json::{json, Map, Value};
 use std::error::Error;
 use tracing::{info, error};
 
+use crate::{
+    config::NotionSyncConfig,
+    models::posting::{ApplicationEvent, StoredPosting},
+};
+
 // use crate::models;
 
+const NOTION_API: &str = "https://api.notion.com/v1";
+
 pub fn notion_client_init(key: String) -> Result<Client, Box<dyn Error>> {
     info!("Initializing Notion client");
     
@@ -121,3 +130,73 @@ pub async fn _retrive_db(client: &reqwest::Client, db_id: &String) -> Result<Str
         }
     }
 }
+
+/// Creates or updates a posting's page in the job alert database after its
+/// application moved along, returning the page id to update next time.
+pub async fn sync_job_application(
+    client: &Client,
+    db_id: &str,
+    config: &NotionSyncConfig,
+    posting: &StoredPosting,
+    event: &ApplicationEvent,
+) -> Result<String, anyhow::Error> {
+    let mut properties = Map::new();
+    properties.insert(
+        config.title_property.clone(),
+        json!({ "title": [{ "text": { "content": posting.title } }] }),
+    );
+    properties.insert(
+        config.status_property.clone(),
+        json!({ "select": { "name": status_label(&event.status) } }),
+    );
+    if let (Some(property), Some(company)) = (&config.company_property, &posting.company) {
+        properties.insert(
+            property.clone(),
+            json!({ "rich_text": [{ "text": { "content": company } }] }),
+        );
+    }
+    if let Some(property) = &config.link_property {
+        properties.insert(property.clone(), json!({ "url": posting.link }));
+    }
+    if let Some(property) = &config.date_property {
+        properties.insert(property.clone(), json!({ "date": { "start": event.date } }));
+    }
+
+    let request = match &posting.notion_page_id {
+        Some(page_id) => {
+            info!("Updating Notion page {} for {}", page_id, posting.key);
+            client
+                .patch(format!("{}/pages/{}", NOTION_API, page_id))
+                .json(&json!({ "properties": properties }))
+        }
+        None => {
+            info!("Creating Notion page for {}", posting.key);
+            client.post(format!("{}/pages", NOTION_API)).json(&json!({
+                "parent": { "database_id": db_id },
+                "properties": properties,
+            }))
+        }
+    };
+
+    let response = request.send().await?;
+    let status = response.status();
+    if !status.is_success() {
+        let error_text = response.text().await.unwrap_or_default();
+        return Err(anyhow!("Notion API returned status {}: {}", status, error_text));
+    }
+
+    let page: Value = response.json().await?;
+    page["id"]
+        .as_str()
+        .map(str::to_string)
+        .ok_or_else(|| anyhow!("Notion didn't return a page id"))
+}
+
+/// `applied` is `Applied`, the way select options are usually written.
+fn status_label(status: &str) -> String {
+    let mut chars = status.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
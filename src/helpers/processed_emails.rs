@@ -0,0 +1,48 @@
+use chrono::Utc;
+use mail_parser::MessageParser;
+use sqlx::SqlitePool;
+
+/// The Message-ID of a raw email, the same whichever way the email reached us.
+pub fn message_id(raw: &[u8]) -> Option<String> {
+    MessageParser::default()
+        .parse_headers(raw)
+        .and_then(|message| message.message_id().map(|id| id.trim().to_lowercase()))
+        .filter(|id| !id.is_empty())
+}
+
+pub async fn is_processed(pool: &SqlitePool, message_id: &str) -> Result<bool, anyhow::Error> {
+    let found: Option<String> =
+        sqlx::query_scalar("SELECT message_id FROM processed_emails WHERE message_id = ?")
+            .bind(message_id)
+            .fetch_optional(pool)
+            .await?;
+    Ok(found.is_some())
+}
+
+/// Marks an email as processed, false if something else already claimed it.
+pub async fn claim(
+    pool: &SqlitePool,
+    message_id: &str,
+    payload_id: &str,
+) -> Result<bool, anyhow::Error> {
+    let result = sqlx::query(
+        "INSERT INTO processed_emails (message_id, payload_id, processed_at) VALUES (?, ?, ?)
+         ON CONFLICT(message_id) DO NOTHING",
+    )
+    .bind(message_id)
+    .bind(payload_id)
+    .bind(Utc::now().to_rfc3339())
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Gives a claim back after processing failed, so a retry isn't mistaken for a duplicate.
+pub async fn release(pool: &SqlitePool, message_id: &str) -> Result<(), anyhow::Error> {
+    sqlx::query("DELETE FROM processed_emails WHERE message_id = ?")
+        .bind(message_id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
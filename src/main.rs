@@ -1,17 +1,34 @@
+mod admin;
+mod auth;
+mod config;
+mod error;
 mod helpers;
 mod middlewares;
 mod models;
+mod queue;
+mod replies;
 mod scheduler;
 mod server;
+mod smtp;
 
 use apalis::prelude::Monitor;
+use auth::AdminAuth;
 use axum::Router;
-use helpers::notion;
+use config::AppConfig;
+use helpers::{
+    db,
+    error_reporter::{ErrorReporter, ErrorReporterConfig},
+    imap::{self, Mailbox},
+    notion,
+};
+use queue::WebhookQueue;
 use reqwest::Client;
 use resend_rs::Resend;
 use shuttle_runtime::SecretStore;
-use std::{sync::Arc};
-use tracing::info;
+use smtp::MailListener;
+use sqlx::SqlitePool;
+use std::{sync::Arc, time::Duration};
+use tracing::{error, info};
 use ufv_timesheet_util::{TimesheetConfig, TimesheetService};
 
 #[derive(Clone)]
@@ -22,12 +39,21 @@ pub struct JobAlertAutomationAppData {
 
 #[derive(Clone)]
 pub struct AppData {
+    config: Arc<AppConfig>,
     resend: Resend,
     job_alert: JobAlertAutomationAppData,
+    error_reporter: ErrorReporter,
+    db: SqlitePool,
+    queue: WebhookQueue,
+    auth: AdminAuth,
+    /// Shared with the Cloudflare worker to sign webhook bodies
+    webhook_secret: Option<String>,
+    mailboxes: Vec<Mailbox>,
 }
 pub struct CustomService {
     router: Router,
     monitor: Monitor,
+    mail_listener: Option<MailListener>,
 }
 
 #[shuttle_runtime::main]
@@ -36,6 +62,13 @@ async fn main(
 ) -> Result<CustomService, shuttle_runtime::Error> {
     info!("Starting Rust Webhooks server");
 
+    let config = AppConfig::load(
+        secrets
+            .get("CONFIG_PATH")
+            .unwrap_or_else(|| config::DEFAULT_CONFIG_PATH.to_string()),
+    )
+    .expect("Failed to load config");
+
     let (timesheet_config, timesheet_notion): (TimesheetConfig, Client) = {
         let notion_api_key = secrets.get("TIMESHEET_NOTION_API_KEY").unwrap();
         info!("Initializing Timesheet Notion client");
@@ -62,11 +95,51 @@ async fn main(
 
     let resend = Resend::new(secrets.get("RESEND_API_KEY").unwrap().as_str());
 
+    let error_reporter = {
+        let defaults = ErrorReporterConfig::default();
+        ErrorReporter::new(
+            resend.clone(),
+            ErrorReporterConfig {
+                dedupe_window: secrets
+                    .get("ERROR_DEDUPE_WINDOW_SECS")
+                    .and_then(|secs| secs.parse().ok())
+                    .map(Duration::from_secs)
+                    .unwrap_or(defaults.dedupe_window),
+                max_alerts_per_hour: secrets
+                    .get("ERROR_MAX_ALERTS_PER_HOUR")
+                    .and_then(|max| max.parse().ok())
+                    .unwrap_or(defaults.max_alerts_per_hour),
+                discord_webhook_url: secrets.get("ERROR_DISCORD_WEBHOOK_URL"),
+            },
+        )
+    };
+
+    let pool = db::init_pool(
+        &secrets
+            .get("DATABASE_URL")
+            .unwrap_or_else(|| db::DEFAULT_DATABASE_URL.to_string()),
+    )
+    .await
+    .expect("Failed to initialize database");
+
+    let auth = AdminAuth::new(&config.auth, secrets.get("ADMIN_SIGNING_KEY"), |name| {
+        secrets.get(name)
+    });
+
+    let mailboxes = imap::resolve_mailboxes(&config.mailboxes, |name| secrets.get(name));
+
     info!("Configuring application state");
     let shared_state: Arc<AppData> = Arc::new(AppData {
+        config: Arc::new(config),
         resend: resend.clone(),
         // timesheet: timesheet_app_data,
         job_alert: job_alert_app_data,
+        error_reporter,
+        queue: WebhookQueue::new(pool.clone()),
+        db: pool,
+        auth,
+        webhook_secret: secrets.get("WEBHOOK_SECRET"),
+        mailboxes,
     });
 
     let timesheet_service = TimesheetService::new(timesheet_notion, resend, timesheet_config);
@@ -77,8 +150,20 @@ async fn main(
     );
 
     let monitor = scheduler::build_cron_worker_monitor(shared_state.clone());
-
-    Ok(CustomService { router, monitor })
+    let monitor = queue::register_webhook_worker(monitor, shared_state.clone());
+    tokio::spawn(scheduler::catch_up_missed_runs(shared_state.clone()));
+
+    let mail_listener = shared_state
+        .config
+        .smtp
+        .clone()
+        .map(|smtp_config| MailListener::new(smtp_config, shared_state.clone()));
+
+    Ok(CustomService {
+        router,
+        monitor,
+        mail_listener,
+    })
 }
 
 #[shuttle_runtime::async_trait]
@@ -94,7 +179,15 @@ impl shuttle_runtime::Service for CustomService {
             self.monitor.run().await.unwrap();
         };
 
-        let _res = tokio::join!(http, monitor);
+        let mail = async {
+            if let Some(mail_listener) = self.mail_listener {
+                if let Err(e) = mail_listener.run().await {
+                    error!("Mail listener stopped: {:#}", e);
+                }
+            }
+        };
+
+        let _res = tokio::join!(http, monitor, mail);
 
         Ok(())
     }
@@ -1,3 +1,160 @@
+use std::sync::Arc;
+
+use axum::{
+    body::{self, Body},
+    extract::{Request, State},
+    http::{header::AUTHORIZATION, HeaderMap},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use subtle::ConstantTimeEq;
+use tracing::{info, warn};
+
+use crate::{
+    auth::{Principal, Scope},
+    error::AppError,
+    AppData,
+};
+
+pub async fn admin_auth(
+    State(state): State<Arc<AppData>>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let principal = request
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .and_then(|token| state.auth.authenticate(token));
+
+    let Some(principal) = principal else {
+        warn!("Admin request rejected for {}", request.uri().path());
+        return AppError::Unauthorized("Invalid or missing bearer token".to_string())
+            .into_response();
+    };
+
+    info!(
+        "Admin request to {} by '{}'",
+        request.uri().path(),
+        principal.name
+    );
+    request.extensions_mut().insert(principal);
+    next.run(request).await
+}
+
+/// Route layer for admin routes, must run after `admin_auth` has added the principal.
+pub async fn require_scope(scope: Scope, request: Request, next: Next) -> Response {
+    let allowed = request
+        .extensions()
+        .get::<Principal>()
+        .is_some_and(|principal| principal.has_scope(scope));
+
+    if !allowed {
+        warn!(
+            "Admin request to {} is missing scope {}",
+            request.uri().path(),
+            scope
+        );
+        return AppError::Forbidden(format!("Token is missing the {} scope", scope))
+            .into_response();
+    }
+
+    next.run(request).await
+}
+
+pub const SIGNATURE_HEADER: &str = "x-webhook-signature";
+pub const TIMESTAMP_HEADER: &str = "x-webhook-timestamp";
+
+/// How far a signed request's timestamp can be from now before it's treated as a replay.
+const MAX_SIGNATURE_AGE_SECS: i64 = 5 * 60;
+
+/// Same as axum's default body limit, the handler wouldn't accept anything bigger anyway.
+const MAX_SIGNED_BODY_BYTES: usize = 2 * 1024 * 1024;
+
+/// Checks the HMAC the Cloudflare worker puts on every webhook, over `<timestamp>.<body>`
+/// with the shared `WEBHOOK_SECRET`.
+pub async fn verify_worker_signature(
+    State(state): State<Arc<AppData>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let path = request.uri().path().to_string();
+
+    let Some(secret) = state.webhook_secret.as_deref() else {
+        warn!(
+            "Webhook to {} rejected, WEBHOOK_SECRET is not configured",
+            path
+        );
+        return AppError::Unauthorized("Webhook signing is not configured".to_string())
+            .into_response();
+    };
+
+    let (timestamp, signature) = match signature_headers(request.headers()) {
+        Ok(headers) => headers,
+        Err(e) => {
+            warn!("Webhook to {} rejected: {}", path, e);
+            return e.into_response();
+        }
+    };
+
+    let age = (Utc::now().timestamp() - timestamp).abs();
+    if age > MAX_SIGNATURE_AGE_SECS {
+        warn!("Webhook to {} rejected, timestamp is {}s off", path, age);
+        return AppError::Unauthorized("Stale webhook timestamp".to_string()).into_response();
+    }
+
+    let (parts, body) = request.into_parts();
+    let bytes = match body::to_bytes(body, MAX_SIGNED_BODY_BYTES).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            return AppError::BadRequest(format!("Failed to read body: {}", e)).into_response()
+        }
+    };
+
+    if !verify_signature(secret, timestamp, &bytes, &signature) {
+        warn!("Webhook to {} rejected, invalid signature", path);
+        return AppError::Unauthorized("Invalid signature".to_string()).into_response();
+    }
+
+    next.run(Request::from_parts(parts, Body::from(bytes)))
+        .await
+}
+
+fn signature_headers(headers: &HeaderMap) -> Result<(i64, String), AppError> {
+    let timestamp = headers
+        .get(TIMESTAMP_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .ok_or_else(|| AppError::Unauthorized("Missing timestamp header".to_string()))?
+        .parse::<i64>()
+        .map_err(|_| AppError::Unauthorized("Invalid timestamp header".to_string()))?;
+
+    let signature = headers
+        .get(SIGNATURE_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .ok_or_else(|| AppError::Unauthorized("Missing signature header".to_string()))?;
+
+    Ok((timestamp, signature.to_string()))
+}
+
+fn verify_signature(secret: &str, timestamp: i64, body: &[u8], signature: &str) -> bool {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC can take key of any size");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    let calculated_signature = format!("sha256={}", hex::encode(mac.finalize().into_bytes()));
+
+    // Constant-time comparison of signatures
+    calculated_signature
+        .as_bytes()
+        .ct_eq(signature.as_bytes())
+        .into()
+}
+
 // use std::{sync::Arc, usize};
 
 // use axum::{
@@ -0,0 +1,20 @@
+use serde::Serialize;
+
+/// Raw inbound webhook exactly as it hit the server.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct ArchivedWebhook {
+    pub id: String,
+    pub route: String,
+    /// JSON object of header name to value
+    pub headers: String,
+    pub body: String,
+    pub received_at: String,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct ArchivedWebhookSummary {
+    pub id: String,
+    pub route: String,
+    pub size: i64,
+    pub received_at: String,
+}
@@ -1,6 +1,6 @@
 use std::fmt::Display;
 
-#[derive(serde::Deserialize)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct EmailWebhookData {
     pub from: String,
     pub to: String,
@@ -30,15 +30,40 @@ pub mod optum {
         pub industry: String,
         pub title: String,
         pub primary_city: String,
+        #[serde(default)]
+        pub primary_state: Option<String>,
+        #[serde(default)]
+        pub primary_country: Option<String>,
         pub level: String,
         pub easy_apply: Vec<serde_json::Value>,
         pub internal_url: String,
         pub internal_description: String,
+        /// Other places the same posting is open in
+        #[serde(default)]
+        pub addtnl_locations: Vec<AdditionalLocation>,
+        /// Guesses at the job's occupation, best match first
+        #[serde(default)]
+        pub classifications: Vec<JobClassification>,
+        #[serde(default)]
+        pub custom_fields: Option<CustomFields>,
         // All other fields will be ignored by serde
         #[serde(flatten)]
         pub _other: serde_json::Value,
     }
 
+    impl Job {
+        /// `City, State`, whichever parts are filled in
+        pub fn location(&self) -> String {
+            [Some(self.primary_city.as_str()), self.primary_state.as_deref()]
+                .into_iter()
+                .flatten()
+                .map(str::trim)
+                .filter(|part| !part.is_empty())
+                .collect::<Vec<_>>()
+                .join(", ")
+        }
+    }
+
     impl Display for Job {
         fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
             write!(f, "Title: {}\nCompany: {}\nID: {}\nIndustry: {}\nLocation: {}\nLevel: {}\nInternal URL: {}\n",
@@ -52,7 +77,8 @@ pub mod optum {
         }
     }
 
-    #[derive(Debug, Serialize, Deserialize)]
+    #[derive(Debug, Default, Serialize, Deserialize)]
+    #[serde(default)]
     pub struct AdditionalLocation {
         pub addtnl_city: String,
         pub addtnl_state: String,
@@ -62,7 +88,8 @@ pub mod optum {
         pub addtnl_location: Vec<f64>,
     }
 
-    #[derive(Debug, Serialize, Deserialize)]
+    #[derive(Debug, Default, Serialize, Deserialize)]
+    #[serde(default)]
     pub struct JobClassification {
         pub major_code: String,
         pub major_description: String,
@@ -72,7 +99,8 @@ pub mod optum {
         pub minor_score: f64,
     }
 
-    #[derive(Debug, Serialize, Deserialize)]
+    #[derive(Debug, Default, Serialize, Deserialize)]
+    #[serde(default)]
     pub struct CustomFields {
         pub req_custom_field_3: Option<CustomField>,
         // Add other custom fields as needed
@@ -85,25 +113,285 @@ pub mod optum {
     }
 }
 
+/// Workday's career site api, `/wday/cxs/{tenant}/{site}/jobs`, the same for
+/// every company on Workday.
+pub mod workday {
+    use std::collections::BTreeMap;
+
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Debug, Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct JobsRequest {
+        pub applied_facets: BTreeMap<String, Vec<String>>,
+        pub limit: u32,
+        pub offset: u32,
+        pub search_text: String,
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct JobsResponse {
+        /// Only filled in on the first page, later pages say 0
+        #[serde(default)]
+        pub total: u32,
+        #[serde(default)]
+        pub job_postings: Vec<Job>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct Job {
+        pub title: String,
+        /// e.g. `/job/Toronto-ON/Software-Developer-Intern_R12345`
+        pub external_path: String,
+        /// A city, or `3 Locations` when there's more than one
+        #[serde(default)]
+        pub locations_text: String,
+    }
+}
+
+/// Greenhouse's public job board api, `boards-api.greenhouse.io/v1/boards/{token}/jobs`.
+pub mod greenhouse {
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize)]
+    pub struct JobsResponse {
+        pub jobs: Vec<Job>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct Job {
+        pub id: u64,
+        pub title: String,
+        pub absolute_url: String,
+        pub location: Option<Location>,
+        /// The description as escaped html, only with `content=true`
+        pub content: Option<String>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct Location {
+        pub name: String,
+    }
+}
+
+/// Lever's public postings api, `api.lever.co/v0/postings/{company}?mode=json`.
+pub mod lever {
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct Posting {
+        pub id: String,
+        /// The title
+        pub text: String,
+        pub hosted_url: String,
+        pub description_plain: Option<String>,
+        #[serde(default)]
+        pub categories: Categories,
+        /// `remote`, `hybrid`, `onsite` or `unspecified`
+        pub workplace_type: Option<String>,
+        pub salary_range: Option<SalaryRange>,
+    }
+
+    #[derive(Debug, Default, Deserialize)]
+    pub struct Categories {
+        pub location: Option<String>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct SalaryRange {
+        pub min: f64,
+        pub max: f64,
+        pub currency: String,
+        /// e.g. `per-year-salary` or `per-hour-wage`
+        pub interval: String,
+    }
+}
+
 
-#[derive(PartialEq, Eq, Hash, Debug)]
+#[derive(PartialEq, Eq, Hash, Debug, serde::Serialize)]
 pub enum JobAlertSource {
     Linkedin,
-    // Glassdoor,
+    Glassdoor,
+    Indeed,
+    Handshake,
+    Wellfound,
 }
 
-#[derive(PartialEq, Eq, Hash, Debug)]
+impl JobAlertSource {
+    /// Lowercase name used as a posting's `source`
+    pub fn name(&self) -> &'static str {
+        match self {
+            JobAlertSource::Linkedin => "linkedin",
+            JobAlertSource::Glassdoor => "glassdoor",
+            JobAlertSource::Indeed => "indeed",
+            JobAlertSource::Handshake => "handshake",
+            JobAlertSource::Wellfound => "wellfound",
+        }
+    }
+
+    /// Which job board an alert came from, going by the address that sent it.
+    pub fn from_sender(sender: &str) -> Option<Self> {
+        let domain = sender.rsplit('@').next()?.to_lowercase();
+        let is = |board: &str| domain == board || domain.ends_with(&format!(".{}", board));
+
+        if is("linkedin.com") {
+            Some(JobAlertSource::Linkedin)
+        } else if is("glassdoor.com") {
+            Some(JobAlertSource::Glassdoor)
+        } else if is("indeed.com") {
+            Some(JobAlertSource::Indeed)
+        } else if is("joinhandshake.com") {
+            Some(JobAlertSource::Handshake)
+        } else if is("wellfound.com") || is("angel.co") {
+            Some(JobAlertSource::Wellfound)
+        } else {
+            None
+        }
+    }
+}
+
+/// Where the job is worked from, as labelled by the job board.
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WorkplaceType {
+    Remote,
+    Hybrid,
+    OnSite,
+}
+
+impl WorkplaceType {
+    pub fn from_label(label: &str) -> Option<Self> {
+        match label.trim().to_lowercase().replace(['-', ' '], "").as_str() {
+            "remote" => Some(WorkplaceType::Remote),
+            "hybrid" => Some(WorkplaceType::Hybrid),
+            "onsite" => Some(WorkplaceType::OnSite),
+            _ => None,
+        }
+    }
+}
+
+/// Kind of position, as labelled by the job board.
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobType {
+    FullTime,
+    PartTime,
+    Internship,
+    CoOp,
+    Contract,
+}
+
+impl JobType {
+    pub fn from_label(label: &str) -> Option<Self> {
+        match label
+            .trim()
+            .to_lowercase()
+            .replace(['-', ' ', '_'], "")
+            .as_str()
+        {
+            "fulltime" => Some(JobType::FullTime),
+            "parttime" => Some(JobType::PartTime),
+            "internship" | "intern" => Some(JobType::Internship),
+            "coop" => Some(JobType::CoOp),
+            "contract" | "contractor" => Some(JobType::Contract),
+            _ => None,
+        }
+    }
+}
+
+/// Extra hints an alert shows next to a job.
+#[derive(PartialEq, Eq, Hash, Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobSignal {
+    ActivelyRecruiting,
+    EasyApply,
+    /// Number of your connections working there
+    Connections(u32),
+}
+
+#[derive(PartialEq, Eq, Hash, Debug, serde::Serialize)]
 pub struct ParsedJob {
     pub source: JobAlertSource,
     pub job_id: String,
+    /// Same for every link to the posting, e.g. `linkedin:4241276548`
+    pub key: String,
     pub title: String,
+    pub company: Option<String>,
     pub location: String,
+    pub workplace: Option<WorkplaceType>,
+    /// Pay as the alert shows it, e.g. `$90,000–$120,000 a year`
+    pub salary: Option<String>,
+    pub job_type: Option<JobType>,
+    /// Last day to apply, when the alert says
+    pub deadline: Option<chrono::NaiveDate>,
+    pub signals: Vec<JobSignal>,
+    /// The saved search that produced the alert
+    pub search_term: Option<String>,
     pub link: String,
 }
 
 impl Display for ParsedJob {
-   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-       write!(f, "Source: {:?}\nJob ID: {}\nTitle: {}\nLocation: {}\nLink: {}",
-              self.source, self.job_id, self.title, self.location, self.link)
-   } 
-}
\ No newline at end of file
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Source: {:?}\nJob ID: {}\nTitle: {}\nCompany: {}\nLocation: {}\nSalary: {}\nLink: {}",
+            self.source,
+            self.job_id,
+            self.title,
+            self.company.as_deref().unwrap_or("-"),
+            self.location,
+            self.salary.as_deref().unwrap_or("-"),
+            self.link
+        )
+    }
+}
+
+/// A job from any source, alert email or careers site, in the one shape used
+/// for matching, storing and notifications.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, sqlx::FromRow)]
+pub struct JobPosting {
+    /// Where it was found, e.g. `linkedin` or `optum`
+    pub source: String,
+    /// Canonical key of the posting's link, unique per posting
+    pub key: String,
+    pub title: String,
+    pub company: Option<String>,
+    pub location: String,
+    pub salary: Option<String>,
+    pub link: String,
+    /// Full description, html on most careers sites
+    pub description: Option<String>,
+    /// Last day to apply, `YYYY-MM-DD`
+    pub deadline: Option<String>,
+}
+
+impl From<&ParsedJob> for JobPosting {
+    fn from(job: &ParsedJob) -> Self {
+        Self {
+            source: job.source.name().to_string(),
+            key: job.key.clone(),
+            title: job.title.clone(),
+            company: job.company.clone(),
+            location: job.location.clone(),
+            salary: job.salary.clone(),
+            link: job.link.clone(),
+            description: None,
+            deadline: job.deadline.map(|deadline| deadline.to_string()),
+        }
+    }
+}
+
+/// Postings that look like the same job, e.g. one from a LinkedIn alert and
+/// one from the company's own careers site.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct MergedJob {
+    pub title: String,
+    pub company: Option<String>,
+    pub location: String,
+    pub salary: Option<String>,
+    /// One per source, the first is the one the details above came from
+    pub postings: Vec<JobPosting>,
+}
@@ -1,2 +1,7 @@
+pub mod archive;
 pub mod notion;
-pub mod job;
\ No newline at end of file
+pub mod job;
+pub mod posting;
+pub mod queue;
+pub mod reply;
+pub mod schedule;
@@ -0,0 +1,198 @@
+use serde::{Deserialize, Serialize};
+
+/// A job posting as stored, with what we've learned about it over time.
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct StoredPosting {
+    pub key: String,
+    pub source: String,
+    pub title: String,
+    pub company: Option<String>,
+    pub location: String,
+    pub salary: Option<String>,
+    pub link: String,
+    pub description: Option<String>,
+    /// Last day to apply, `YYYY-MM-DD`
+    pub deadline: Option<String>,
+    /// The careers site or board it's polled from, e.g. `workday:acme`.
+    /// Postings from alert emails don't have one and are never closed.
+    pub feed: Option<String>,
+    pub first_seen_at: String,
+    pub last_seen_at: String,
+    /// When it stopped showing up in its feed
+    pub closed_at: Option<String>,
+    /// When it was first saved, saved postings get emails when they change or close
+    pub saved_at: Option<String>,
+    /// Where the application is at, see `ApplicationStatus`
+    pub status: Option<String>,
+    pub status_changed_at: Option<String>,
+    /// Its page in the job alert Notion database, once synced
+    pub notion_page_id: Option<String>,
+    // from the description, see `JobAnalysis`
+    pub pay_min: Option<f64>,
+    pub pay_max: Option<f64>,
+    pub pay_currency: Option<String>,
+    pub pay_period: Option<String>,
+    pub term_months: Option<i64>,
+    pub term_start: Option<String>,
+    pub years_experience: Option<i64>,
+    /// Comma separated
+    pub skills: Option<String>,
+}
+
+/// One field of a posting changing between polls.
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct PostingRevision {
+    pub id: i64,
+    pub key: String,
+    /// title, location, salary, description, deadline or status
+    pub field: String,
+    pub old_value: Option<String>,
+    pub new_value: Option<String>,
+    pub changed_at: String,
+}
+
+/// A saved posting that changed or closed, with what changed.
+#[derive(Debug, Clone, Serialize)]
+pub struct PostingUpdate {
+    pub posting: StoredPosting,
+    pub revisions: Vec<PostingRevision>,
+}
+
+/// How far along applying to a saved posting is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ApplicationStatus {
+    Saved,
+    Applied,
+    Interviewing,
+    Rejected,
+    Offer,
+}
+
+impl ApplicationStatus {
+    pub const ALL: [ApplicationStatus; 5] = [
+        ApplicationStatus::Saved,
+        ApplicationStatus::Applied,
+        ApplicationStatus::Interviewing,
+        ApplicationStatus::Rejected,
+        ApplicationStatus::Offer,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ApplicationStatus::Saved => "saved",
+            ApplicationStatus::Applied => "applied",
+            ApplicationStatus::Interviewing => "interviewing",
+            ApplicationStatus::Rejected => "rejected",
+            ApplicationStatus::Offer => "offer",
+        }
+    }
+
+    /// Waiting to hear back, so worth a nudge when nothing happens for a while
+    pub fn is_pending(self) -> bool {
+        matches!(self, ApplicationStatus::Applied | ApplicationStatus::Interviewing)
+    }
+}
+
+/// A posting moving to a status, like applying or getting an interview.
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct ApplicationEvent {
+    pub id: i64,
+    pub key: String,
+    /// saved, applied, interviewing, rejected or offer
+    pub status: String,
+    pub note: Option<String>,
+    /// When it happened, `YYYY-MM-DD`
+    pub date: String,
+    pub created_at: String,
+}
+
+/// What a hide rule matches on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleKind {
+    /// The same company, compared like when matching postings up
+    Company,
+    /// Titles containing the value, case-insensitive
+    Title,
+}
+
+impl RuleKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            RuleKind::Company => "company",
+            RuleKind::Title => "title",
+        }
+    }
+}
+
+/// Postings matching a rule are left out of digests.
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct FilterRule {
+    pub id: i64,
+    /// company or title, see `RuleKind`
+    pub kind: String,
+    pub value: String,
+    pub created_at: String,
+}
+
+/// What a job's description says about it, anything it doesn't mention is left out.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct JobAnalysis {
+    pub pay: Option<PayRange>,
+    pub term: Option<WorkTerm>,
+    /// Fewest years of experience asked for
+    pub years_experience: Option<u32>,
+    /// Names from the skill dictionary, in dictionary order
+    pub skills: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PayRange {
+    pub min: f64,
+    pub max: f64,
+    /// `CAD`, `USD`... only when spelled out, a bare `$` could be either
+    pub currency: Option<String>,
+    pub period: PayPeriod,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PayPeriod {
+    Hour,
+    Day,
+    Week,
+    Month,
+    Year,
+}
+
+impl PayPeriod {
+    pub fn name(self) -> &'static str {
+        match self {
+            PayPeriod::Hour => "hour",
+            PayPeriod::Day => "day",
+            PayPeriod::Week => "week",
+            PayPeriod::Month => "month",
+            PayPeriod::Year => "year",
+        }
+    }
+
+    /// Roughly how many of these make a year of full-time work.
+    pub fn per_year(self) -> f64 {
+        match self {
+            PayPeriod::Hour => 2080.0,
+            PayPeriod::Day => 260.0,
+            PayPeriod::Week => 52.0,
+            PayPeriod::Month => 12.0,
+            PayPeriod::Year => 1.0,
+        }
+    }
+}
+
+/// How long a co-op or internship runs and when it starts.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorkTerm {
+    pub months: Option<u32>,
+    /// e.g. `September 2025` or `Fall 2025`
+    pub start: Option<String>,
+}
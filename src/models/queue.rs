@@ -0,0 +1,38 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::models::job::EmailWebhookData;
+
+/// Inbound webhook persisted to the queue, processed later by a worker.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookJob {
+    pub id: String,
+    pub received_at: DateTime<Utc>,
+    pub payload: WebhookPayload,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "data", rename_all = "snake_case")]
+pub enum WebhookPayload {
+    JobAlertEmail(EmailWebhookData),
+}
+
+impl WebhookPayload {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            WebhookPayload::JobAlertEmail(_) => "job_alert_email",
+        }
+    }
+}
+
+/// A webhook that ran out of retries (or was rejected outright) and can be replayed.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct DeadLetter {
+    pub id: String,
+    pub kind: String,
+    pub payload: String,
+    pub error: String,
+    pub attempts: i64,
+    pub failed_at: String,
+    pub replayed_at: Option<String>,
+}
@@ -0,0 +1,32 @@
+use serde::Serialize;
+
+use super::posting::{ApplicationStatus, RuleKind};
+
+/// A reply to a jobs digest, read as one command per line.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ParsedReply {
+    /// Address in the From header, lowercased
+    pub sender: Option<String>,
+    /// The digest replied to, from `[digest <id>]` in the subject
+    pub digest: Option<i64>,
+    pub commands: Vec<ReplyCommand>,
+    /// Lines above the quoted digest that aren't commands
+    pub unknown: Vec<String>,
+}
+
+/// Jobs are referred to by their number in the digest.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum ReplyCommand {
+    /// `save 3`, `applied 3 5`, `rejected 4`...
+    Status {
+        status: ApplicationStatus,
+        codes: Vec<u32>,
+    },
+    /// `unsave 3`
+    Unsave { codes: Vec<u32> },
+    /// `hide company Acme`, `hide title senior`
+    Hide { kind: RuleKind, value: String },
+    /// `unhide company Acme`
+    Unhide { kind: RuleKind, value: String },
+}
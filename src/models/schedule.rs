@@ -0,0 +1,16 @@
+use serde::Serialize;
+
+/// One attempt at running a scheduled job.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct CronRun {
+    pub id: i64,
+    pub schedule: String,
+    pub scheduled_for: String,
+    pub started_at: String,
+    pub finished_at: Option<String>,
+    /// running, succeeded or failed
+    pub outcome: String,
+    pub error: Option<String>,
+    /// cron, catch_up or manual
+    pub trigger: String,
+}
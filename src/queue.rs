@@ -0,0 +1,385 @@
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use apalis::{
+    layers::retry::{
+        backoff::{ExponentialBackoff, ExponentialBackoffMaker, MakeBackoff},
+        HasherRng, RetryPolicy,
+    },
+    prelude::*,
+};
+use apalis_sql::sqlite::SqliteStorage;
+use axum::http::HeaderMap;
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use chrono::Utc;
+use resend_rs::types::Attachment;
+use serde_json::{json, Value};
+use sqlx::SqlitePool;
+use tracing::{info, warn};
+
+use crate::{
+    error::AppError,
+    helpers::{
+        archive, email,
+        error_reporter::ErrorContext,
+        job_checker::{self, JobAlertEmailHandler},
+        job_store, processed_emails,
+    },
+    models::{
+        job::EmailWebhookData,
+        queue::{DeadLetter, WebhookJob, WebhookPayload},
+    },
+    replies, AppData,
+};
+
+/// Retries after the first attempt before a webhook is dead-lettered.
+const WEBHOOK_RETRIES: usize = 5;
+/// Wait before the first retry, doubling for each one after it
+const WEBHOOK_MIN_BACKOFF: Duration = Duration::from_secs(30);
+const WEBHOOK_MAX_BACKOFF: Duration = Duration::from_secs(10 * 60);
+
+pub const JOB_ALERT_ROUTE: &str = "/cloudflare-job-alert-reciever";
+/// Archive route for mail received by the built-in SMTP/LMTP listener
+pub const SMTP_ROUTE: &str = "smtp";
+/// Archive route for mail fetched by the IMAP poller
+pub const IMAP_ROUTE: &str = "imap";
+
+#[derive(Clone)]
+pub struct WebhookQueue {
+    storage: SqliteStorage<WebhookJob>,
+    pool: SqlitePool,
+}
+
+impl WebhookQueue {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self {
+            storage: SqliteStorage::new(pool.clone()),
+            pool,
+        }
+    }
+
+    /// Queues a webhook under the id it was archived with.
+    pub async fn enqueue(&self, id: String, payload: WebhookPayload) -> Result<(), anyhow::Error> {
+        self.push(WebhookJob {
+            id,
+            received_at: Utc::now(),
+            payload,
+        })
+        .await
+    }
+
+    async fn push(&self, job: WebhookJob) -> Result<(), anyhow::Error> {
+        info!("Queueing {} webhook {}", job.payload.kind(), job.id);
+        let mut storage = self.storage.clone();
+        storage.push(job).await?;
+        Ok(())
+    }
+
+    pub async fn dead_letters(&self) -> Result<Vec<DeadLetter>, anyhow::Error> {
+        Ok(sqlx::query_as::<_, DeadLetter>(
+            "SELECT * FROM dead_letters WHERE replayed_at IS NULL ORDER BY failed_at DESC",
+        )
+        .fetch_all(&self.pool)
+        .await?)
+    }
+
+    /// Puts a dead-lettered webhook back on the queue under its original id.
+    pub async fn replay_dead_letter(&self, id: &str) -> Result<Option<String>, anyhow::Error> {
+        let Some(dead_letter) =
+            sqlx::query_as::<_, DeadLetter>("SELECT * FROM dead_letters WHERE id = ?")
+                .bind(id)
+                .fetch_optional(&self.pool)
+                .await?
+        else {
+            return Ok(None);
+        };
+
+        let job: WebhookJob = serde_json::from_str(&dead_letter.payload)?;
+        self.push(job).await?;
+
+        sqlx::query("UPDATE dead_letters SET replayed_at = ? WHERE id = ?")
+            .bind(Utc::now().to_rfc3339())
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(Some(dead_letter.id))
+    }
+
+    async fn dead_letter(&self, job: &WebhookJob, error: &AppError, attempts: usize) {
+        warn!("Dead-lettering webhook {}: {}", job.id, error);
+        let result = sqlx::query(
+            "INSERT INTO dead_letters (id, kind, payload, error, attempts, failed_at, replayed_at)
+             VALUES (?, ?, ?, ?, ?, ?, NULL)
+             ON CONFLICT(id) DO UPDATE SET
+                error = excluded.error,
+                attempts = excluded.attempts,
+                failed_at = excluded.failed_at,
+                replayed_at = NULL",
+        )
+        .bind(&job.id)
+        .bind(job.payload.kind())
+        .bind(serde_json::to_string(job).unwrap_or_default())
+        .bind(error.to_string())
+        .bind(attempts as i64)
+        .bind(Utc::now().to_rfc3339())
+        .execute(&self.pool)
+        .await;
+
+        if let Err(e) = result {
+            warn!("Failed to store dead letter {}: {}", job.id, e);
+        }
+    }
+
+    async fn clear_dead_letter(&self, id: &str) {
+        if let Err(e) = sqlx::query("DELETE FROM dead_letters WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+        {
+            warn!("Failed to clear dead letter {}: {}", id, e);
+        }
+    }
+}
+
+pub async fn process_webhook(
+    job: WebhookJob,
+    state: Data<Arc<AppData>>,
+    attempt: Attempt,
+) -> Result<(), Error> {
+    info!(
+        "Processing {} webhook {} (attempt {})",
+        job.payload.kind(),
+        job.id,
+        attempt.current()
+    );
+
+    let error = match run_payload(&state, &job.id, &job.payload).await {
+        Ok(()) => {
+            // a retry that succeeded shouldn't leave a dead letter behind
+            state.queue.clear_dead_letter(&job.id).await;
+            return Ok(());
+        }
+        Err(e) => e,
+    };
+
+    let context = ErrorContext::job("webhook-queue").with_payload_id(job.id.clone());
+
+    // bad input won't get better by retrying
+    if !error.status().is_server_error() {
+        state.queue.dead_letter(&job, &error, attempt.current()).await;
+        state.error_reporter.report(context, &error).await;
+        return Err(Error::Abort(Arc::new(Box::new(error))));
+    }
+
+    // attempts count from 1, so the last one is WEBHOOK_RETRIES + 1
+    if attempt.current() > WEBHOOK_RETRIES {
+        state.queue.dead_letter(&job, &error, attempt.current()).await;
+        state.error_reporter.report(context, &error).await;
+    }
+
+    Err(Error::Failed(Arc::new(Box::new(error))))
+}
+
+/// Archives, validates and queues an inbound webhook, returning the id it was archived under.
+pub async fn accept_webhook(
+    state: &AppData,
+    route: &str,
+    headers: &HeaderMap,
+    body: &[u8],
+) -> Result<String, AppError> {
+    let context = ErrorContext::route(route);
+
+    // archive before anything can fail so every payload can be replayed later
+    let id = match archive::archive_webhook(&state.db, route, headers, body).await {
+        Ok(id) => id,
+        Err(e) => {
+            return Err(AppError::Internal(e).reported(&state.error_reporter, context));
+        }
+    };
+    let context = context.with_payload_id(id.clone());
+
+    let payload = decode_payload(route, body)?;
+    match &payload {
+        WebhookPayload::JobAlertEmail(payload) => {
+            info!(
+                "From: {}, To: {}, Size: {}",
+                &payload.from, &payload.to, &payload.size
+            );
+
+            // reject garbage up front, the worker would only dead-letter it
+            if let Err(e) = STANDARD.decode(&payload.raw_content) {
+                return Err(AppError::BadRequest(format!(
+                    "Failed to decode base64 content: {}",
+                    e
+                )));
+            }
+        }
+    }
+
+    match state.queue.enqueue(id.clone(), payload).await {
+        Ok(()) => Ok(id),
+        Err(e) => Err(AppError::Internal(e).reported(&state.error_reporter, context)),
+    }
+}
+
+/// Maps an archived request back to the payload its route would have queued.
+pub fn decode_payload(route: &str, body: &[u8]) -> Result<WebhookPayload, AppError> {
+    match route {
+        JOB_ALERT_ROUTE | SMTP_ROUTE | IMAP_ROUTE => {
+            let payload: EmailWebhookData = serde_json::from_slice(body)
+                .map_err(|e| AppError::BadRequest(format!("Invalid job alert payload: {}", e)))?;
+            Ok(WebhookPayload::JobAlertEmail(payload))
+        }
+        _ => Err(AppError::BadRequest(format!(
+            "No webhook handler for route {}",
+            route
+        ))),
+    }
+}
+
+async fn run_payload(state: &AppData, id: &str, payload: &WebhookPayload) -> Result<(), AppError> {
+    match payload {
+        WebhookPayload::JobAlertEmail(payload) => {
+            let message_id = STANDARD
+                .decode(&payload.raw_content)
+                .ok()
+                .and_then(|raw| processed_emails::message_id(&raw));
+
+            // the same alert can come in through cloudflare, smtp and imap
+            let Some(message_id) = message_id else {
+                return handle_email(state, payload).await;
+            };
+            if !processed_emails::claim(&state.db, &message_id, id).await? {
+                info!("Email {} was already processed, skipping {}", message_id, id);
+                return Ok(());
+            }
+
+            let result = handle_email(state, payload).await;
+            if result.is_err() {
+                if let Err(e) = processed_emails::release(&state.db, &message_id).await {
+                    warn!("Failed to release processed email {}: {}", message_id, e);
+                }
+            }
+            result
+        }
+    }
+}
+
+/// Replies to a digest go to the replies address, everything else is a job alert.
+async fn handle_email(state: &AppData, payload: &EmailWebhookData) -> Result<(), AppError> {
+    if replies::is_reply(state, payload) {
+        replies::process_reply(state, payload).await.map(|_| ())
+    } else {
+        process_job_alert_email(state, payload).await.map(|_| ())
+    }
+}
+
+/// Runs an archived webhook through the current handlers right away, skipping
+/// the queue so the result can be returned to whoever asked for the replay.
+pub async fn replay_payload(
+    state: &AppData,
+    payload: &WebhookPayload,
+    dry_run: bool,
+) -> Result<Value, AppError> {
+    match payload {
+        WebhookPayload::JobAlertEmail(payload) if replies::is_reply(state, payload) => {
+            if dry_run {
+                Ok(json!({ "reply": replies::parse_reply(payload)? }))
+            } else {
+                Ok(json!({ "done": replies::process_reply(state, payload).await? }))
+            }
+        }
+        WebhookPayload::JobAlertEmail(payload) => {
+            let handler = if dry_run {
+                parse_job_alert_email(payload)?.1
+            } else {
+                process_job_alert_email(state, payload).await?
+            };
+            Ok(json!({ "jobs": handler.results().values().collect::<Vec<_>>() }))
+        }
+    }
+}
+
+pub async fn process_job_alert_email(
+    state: &AppData,
+    payload: &EmailWebhookData,
+) -> Result<JobAlertEmailHandler, AppError> {
+    let (email_bytes, handler) = parse_job_alert_email(payload)?;
+    // alerts don't come with descriptions, so only the hide rules apply
+    let rules = job_store::rules(&state.db).await?;
+    let postings = job_checker::filter_postings(
+        handler.postings(),
+        &HashMap::new(),
+        &state.config.filters,
+        &rules,
+    );
+    job_store::record(&state.db, &postings, None).await?;
+    let found_jobs =
+        job_checker::merge_with_seen(&state.db, &state.config.matching, postings).await?;
+
+    let subject = format!("Job alert processing from {}", payload.from);
+    let attachment = Attachment::from_content(email_bytes)
+        .with_content_type("txt")
+        .with_filename("email_bytes");
+    if found_jobs.is_empty() {
+        email::send_email(
+            &state.resend,
+            "No jobs found in email",
+            Some(subject.as_str()),
+            Some(attachment),
+        )
+        .await?;
+    } else {
+        replies::send_digest(state, &found_jobs, &subject, Some(attachment)).await?;
+    }
+
+    info!("Processed job alert email, {} jobs found", found_jobs.len());
+    Ok(handler)
+}
+
+/// Decodes and parses a job alert email without sending anything, also used for dry-run replays.
+pub fn parse_job_alert_email(
+    payload: &EmailWebhookData,
+) -> Result<(Vec<u8>, JobAlertEmailHandler), AppError> {
+    // Decode the email content
+    let email_bytes = STANDARD
+        .decode(&payload.raw_content)
+        .map_err(|e| AppError::BadRequest(format!("Failed to decode base64 content: {}", e)))?;
+    let email_content = String::from_utf8(email_bytes.clone())
+        .map_err(|e| AppError::BadRequest(format!("Failed to parse email as UTF-8: {}", e)))?;
+
+    info!(
+        "Email content: {}",
+        email_content.chars().take(200).collect::<String>()
+    );
+
+    let handler = JobAlertEmailHandler::new(&email_bytes)
+        .map_err(|e| AppError::UnprocessableEntity(e.to_string()))?;
+
+    Ok((email_bytes, handler))
+}
+
+pub fn register_webhook_worker(monitor: Monitor, shared_state: Arc<AppData>) -> Monitor {
+    let storage = shared_state.queue.storage.clone();
+
+    monitor.register({
+        WorkerBuilder::new("webhook-queue")
+            .data(shared_state)
+            .retry(RetryPolicy::retries(WEBHOOK_RETRIES).with_backoff(webhook_backoff()))
+            .backend(storage)
+            .build_fn(process_webhook)
+    })
+}
+
+/// Spreads the retries out over about 15 minutes, so they can outlast a short
+/// Resend or Notion outage instead of all failing within the same second.
+fn webhook_backoff() -> ExponentialBackoff {
+    ExponentialBackoffMaker::new(
+        WEBHOOK_MIN_BACKOFF,
+        WEBHOOK_MAX_BACKOFF,
+        0.1,
+        HasherRng::default(),
+    )
+    .expect("webhook backoff bounds are valid")
+    .make_backoff()
+}
@@ -0,0 +1,222 @@
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use chrono::Utc;
+use resend_rs::types::Attachment;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::{
+    admin,
+    error::AppError,
+    helpers::{
+        email,
+        job_checker::{self, commands},
+        job_store,
+    },
+    models::{
+        job::{EmailWebhookData, MergedJob},
+        posting::{ApplicationStatus, StoredPosting},
+        reply::{ParsedReply, ReplyCommand},
+    },
+    AppData,
+};
+
+/// Emails a digest of jobs. With replies set up the digest is recorded, so a
+/// reply can refer to its jobs by number, and goes out with a Reply-To of the
+/// replies address plus a token for this digest.
+pub async fn send_digest(
+    state: &AppData,
+    jobs: &[MergedJob],
+    subject: &str,
+    attachment: Option<Attachment>,
+) -> Result<(), anyhow::Error> {
+    let mut content = job_checker::jobs_html(jobs);
+    let Some(replies) = &state.config.replies else {
+        email::send_email(&state.resend, &content, Some(subject), attachment).await?;
+        return Ok(());
+    };
+
+    // the first posting is the one the job's details came from
+    let keys: Vec<&str> = jobs
+        .iter()
+        .map(|job| job.postings[0].key.as_str())
+        .collect();
+    let token = Uuid::new_v4().simple().to_string();
+    let digest = job_store::new_digest(&state.db, subject, &token, &keys).await?;
+    content.push_str(&format!("<p>{}</p>", commands::HELP));
+
+    let subject = format!("{} [digest {}]", subject, digest);
+    email::send_email_with_reply_to(
+        &state.resend,
+        &content,
+        Some(subject.as_str()),
+        attachment,
+        Some(replies.reply_to(&token).as_str()),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Whether a forwarded email was sent to the replies address rather than
+/// being a job alert.
+pub fn is_reply(state: &AppData, payload: &EmailWebhookData) -> bool {
+    state
+        .config
+        .replies
+        .as_ref()
+        .is_some_and(|replies| replies.is_for(&payload.to))
+}
+
+/// Decodes and reads a reply without running it, also used for dry-run replays.
+pub fn parse_reply(payload: &EmailWebhookData) -> Result<ParsedReply, AppError> {
+    let raw = STANDARD
+        .decode(&payload.raw_content)
+        .map_err(|e| AppError::BadRequest(format!("Failed to decode base64 content: {}", e)))?;
+    commands::parse(&raw).map_err(|e| AppError::UnprocessableEntity(e.to_string()))
+}
+
+/// Runs the commands in a reply and emails back what was done. Replies are
+/// dropped unless they're from one of `replies.senders` and sent to a digest's
+/// token, the From header alone is easy to fake. Nothing is run unless the
+/// subject names that same digest.
+pub async fn process_reply(
+    state: &AppData,
+    payload: &EmailWebhookData,
+) -> Result<Vec<String>, AppError> {
+    let reply = parse_reply(payload)?;
+    let Some(replies) = &state.config.replies else {
+        return Ok(Vec::new());
+    };
+    let sender = reply.sender.as_deref().unwrap_or("unknown sender");
+    if !replies.allows(sender) {
+        warn!("Ignoring reply from {}, not an allowed sender", sender);
+        return Ok(Vec::new());
+    }
+    let token_digest = match replies.token(&payload.to) {
+        Some(token) => job_store::digest_for_token(&state.db, token).await?,
+        None => None,
+    };
+    let Some(token_digest) = token_digest else {
+        warn!(
+            "Ignoring reply from {} to {}, no digest has that token",
+            sender, payload.to
+        );
+        return Ok(Vec::new());
+    };
+
+    // job numbers only mean something within one digest, so don't guess
+    let (subject, done) = if reply.digest != Some(token_digest) {
+        let question = format!(
+            "Which digest? Keep the [digest {}] in the subject when replying, nothing was changed.",
+            token_digest
+        );
+        ("Reply not processed".to_string(), vec![question])
+    } else {
+        let mut done = Vec::new();
+        for command in &reply.commands {
+            done.extend(run_command(state, token_digest, command).await?);
+        }
+        for line in &reply.unknown {
+            done.push(format!("didn't understand \"{}\"", line));
+        }
+        if done.is_empty() || !reply.unknown.is_empty() {
+            done.push(commands::HELP.to_string());
+        }
+        let subject = format!("Reply to digest {} processed", token_digest);
+        (subject, done)
+    };
+
+    email::send_email(
+        &state.resend,
+        &done.join("\n"),
+        Some(subject.as_str()),
+        None,
+    )
+    .await?;
+
+    info!("Answered reply from {}: {}", sender, subject);
+    Ok(done)
+}
+
+/// What happened, one line per job or rule.
+async fn run_command(
+    state: &AppData,
+    digest: i64,
+    command: &ReplyCommand,
+) -> Result<Vec<String>, anyhow::Error> {
+    let mut done = Vec::new();
+    match command {
+        ReplyCommand::Status { status, codes } => {
+            for &code in codes {
+                let posting = match digest_posting(state, digest, code).await? {
+                    Ok(posting) => posting,
+                    Err(missing) => {
+                        done.push(missing);
+                        continue;
+                    }
+                };
+                // saving again shouldn't knock an application back to saved
+                if *status == ApplicationStatus::Saved {
+                    admin::save(state, &posting.key).await?;
+                } else {
+                    let today = Utc::now().date_naive();
+                    admin::update_application(state, &posting.key, *status, None, today).await?;
+                }
+                done.push(format!(
+                    "{} {}: {}",
+                    status.name(),
+                    code,
+                    describe(&posting)
+                ));
+            }
+        }
+        ReplyCommand::Unsave { codes } => {
+            for &code in codes {
+                match digest_posting(state, digest, code).await? {
+                    Ok(posting) => {
+                        job_store::unsave(&state.db, &posting.key).await?;
+                        done.push(format!("unsaved {}: {}", code, describe(&posting)));
+                    }
+                    Err(missing) => done.push(missing),
+                }
+            }
+        }
+        ReplyCommand::Hide { kind, value } => {
+            if job_store::add_rule(&state.db, *kind, value).await? {
+                done.push(format!("hiding {} {}", kind.name(), value));
+            } else {
+                done.push(format!("already hiding {} {}", kind.name(), value));
+            }
+        }
+        ReplyCommand::Unhide { kind, value } => {
+            if job_store::remove_rule(&state.db, *kind, value).await? {
+                done.push(format!("no longer hiding {} {}", kind.name(), value));
+            } else {
+                done.push(format!("wasn't hiding {} {}", kind.name(), value));
+            }
+        }
+    }
+    Ok(done)
+}
+
+/// The posting numbered `code` in a digest, or why there isn't one.
+async fn digest_posting(
+    state: &AppData,
+    digest: i64,
+    code: u32,
+) -> Result<Result<StoredPosting, String>, anyhow::Error> {
+    let Some(key) = job_store::digest_key(&state.db, digest, code).await? else {
+        return Ok(Err(format!("no job {} in digest {}", code, digest)));
+    };
+    match job_store::get(&state.db, &key).await? {
+        Some(posting) => Ok(Ok(posting)),
+        None => Ok(Err(format!("job {} ({}) is gone", code, key))),
+    }
+}
+
+fn describe(posting: &StoredPosting) -> String {
+    format!(
+        "{} at {}",
+        posting.title,
+        posting.company.as_deref().unwrap_or("unknown company")
+    )
+}
@@ -1,38 +1,124 @@
 use apalis::{layers::retry::RetryPolicy, prelude::*};
-use apalis_cron::{CronStream, Schedule};
-use chrono::{DateTime, Local, Utc};
+use apalis_cron::CronStream;
+use axum::http::{HeaderMap, HeaderValue};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use chrono::{DateTime, Days, Utc};
 use serde::{Deserialize, Serialize};
-use std::{str::FromStr, sync::Arc};
+use std::{collections::HashSet, future::Future, sync::Arc};
+use tokio::sync::mpsc;
+use tracing::{error, info, warn};
 
 use crate::{
-    helpers::{email, job_checker},
-    AppData,
+    config::{CatchUpPolicy, ScheduleConfig, ScheduledTask},
+    helpers::{
+        cron_history::{self, RunTrigger},
+        email,
+        error_reporter::ErrorContext,
+        imap::Mailbox,
+        job_checker::{
+            self,
+            boards::{self, JobBoardConfig},
+            scheduler::optum_posting,
+            workday::{self, WorkdayConfig},
+        },
+        job_store, processed_emails,
+    },
+    models::{
+        job::{EmailWebhookData, JobPosting},
+        posting::PostingUpdate,
+    },
+    queue::{self, IMAP_ROUTE},
+    replies, AppData,
 };
 
+/// Per-run options, and a channel to report progress to whoever triggered a manual run.
+#[derive(Clone, Default)]
+pub struct RunContext {
+    /// Do everything except sending notifications
+    pub dry_run: bool,
+    progress: Option<mpsc::UnboundedSender<String>>,
+}
+
+impl RunContext {
+    pub fn manual(dry_run: bool, progress: mpsc::UnboundedSender<String>) -> Self {
+        Self {
+            dry_run,
+            progress: Some(progress),
+        }
+    }
+
+    pub fn log(&self, message: impl Into<String>) {
+        let message = message.into();
+        info!("{}", message);
+        if let Some(progress) = &self.progress {
+            // the listener going away shouldn't stop the job
+            let _ = progress.send(message);
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct CronjobData {
-    pub message: String,
-    pub _app_data: Arc<AppData>,
+    pub schedule: ScheduleConfig,
+    pub app_data: Arc<AppData>,
 }
 impl CronjobData {
-    async fn execute(&self, _item: Reminder) {
-        println!("{} from CronjobData::execute()!", &self.message);
-        let optum_jobs = job_checker::scheduler::optum().await.unwrap();
-
-        if !optum_jobs.is_empty() {
-            println!("found jobs !!!");
-            let _email_res = email::send_email(
-                &self._app_data.resend,
-                format!("{:?}", optum_jobs).as_str(),
-                Some("FOUND AN OPTUM JOB !!!"),
-                None
-            )
+    async fn execute(&self, item: Reminder) -> Result<(), Error> {
+        let ctx = RunContext::default();
+        run_recorded(&self.schedule, &self.app_data, item.0, RunTrigger::Cron, &ctx)
             .await
-            .unwrap();
-        } else {
-            println!("no job :(");
+            .map_err(|e| Error::Failed(Arc::new(e.into())))
+    }
+}
+
+/// Runs a schedule's task with a row in the run history, reporting failures.
+pub async fn run_recorded(
+    schedule: &ScheduleConfig,
+    state: &AppData,
+    scheduled_for: DateTime<Utc>,
+    trigger: RunTrigger,
+    ctx: &RunContext,
+) -> Result<(), anyhow::Error> {
+    ctx.log(format!(
+        "Running scheduled job '{}' ({:?}) for {} [{:?}{}]",
+        schedule.name,
+        schedule.task,
+        scheduled_for,
+        trigger,
+        if ctx.dry_run { ", dry run" } else { "" }
+    ));
+
+    // history is best effort, a broken database shouldn't stop the job itself
+    let run_id = match cron_history::start_run(&state.db, &schedule.name, scheduled_for, trigger)
+        .await
+    {
+        Ok(run_id) => Some(run_id),
+        Err(e) => {
+            error!("Failed to record start of '{}': {:#}", schedule.name, e);
+            None
+        }
+    };
+
+    let result = run_task(schedule.task, state, ctx).await;
+    let error = result.as_ref().err().map(|e| format!("{:#}", e));
+
+    if let Some(run_id) = run_id {
+        if let Err(e) = cron_history::finish_run(&state.db, run_id, error.as_deref()).await {
+            error!("Failed to record end of '{}': {:#}", schedule.name, e);
         }
     }
+
+    match error {
+        None => ctx.log(format!("Scheduled job '{}' finished", schedule.name)),
+        Some(error) => {
+            state
+                .error_reporter
+                .report(ErrorContext::job(&schedule.name), error)
+                .await
+        }
+    }
+
+    result
 }
 
 #[derive(Default, Debug, Clone, Serialize, Deserialize)]
@@ -43,25 +129,581 @@ impl From<DateTime<Utc>> for Reminder {
     }
 }
 
-pub async fn say_hello_world(job: Reminder, svc: Data<CronjobData>) {
-    // println!("Hello world from send_reminder()!");
+pub async fn run_scheduled_job(job: Reminder, svc: Data<CronjobData>) -> Result<(), Error> {
     // this executes CronjobData::execute()
-    svc.execute(job).await;
+    svc.execute(job).await
 }
 
-pub fn build_cron_worker_monitor(shared_state: Arc<AppData>) -> Monitor {
-    // Format: sec min hour day_of_month month day_of_week (year)
-    let schedule = Schedule::from_str("0 0 0 1-31/2 * *").expect("Couldn't start the scheduler!");
-    let cron_service_ext = CronjobData {
-        message: "Hello world".to_string(),
-        _app_data: shared_state.clone(),
+pub async fn run_task(
+    task: ScheduledTask,
+    state: &AppData,
+    ctx: &RunContext,
+) -> Result<(), anyhow::Error> {
+    match task {
+        ScheduledTask::Optum => check_optum(state, ctx).await,
+        ScheduledTask::HealthCheck => health_check(state, ctx).await,
+        ScheduledTask::ImapPoll => poll_mailboxes(state, ctx).await,
+        ScheduledTask::Workday => check_workday(state, ctx).await,
+        ScheduledTask::JobBoards => check_job_boards(state, ctx).await,
+        ScheduledTask::JobReminders => send_job_reminders(state, ctx).await,
+    }
+}
+
+async fn check_optum(state: &AppData, ctx: &RunContext) -> Result<(), anyhow::Error> {
+    let postings: Vec<_> = job_checker::scheduler::optum(&state.config.optum)
+        .await?
+        .iter()
+        .map(optum_posting)
+        .collect();
+
+    if postings.is_empty() {
+        ctx.log("no optum jobs found");
+        return Ok(());
+    }
+
+    let optum = &state.config.optum;
+    let got_everything = postings.len() < (optum.max_pages * optum.page_size) as usize;
+    let (changed, updates) = track(state, ctx, "optum", &postings, got_everything).await?;
+    notify_updates(state, &updates).await?;
+
+    let postings: Vec<_> = postings
+        .into_iter()
+        .filter(|posting| changed.contains(&posting.key))
+        .collect();
+    if postings.is_empty() {
+        ctx.log("no new or changed optum jobs");
+        return Ok(());
+    }
+    report_jobs(state, ctx, postings, "FOUND AN OPTUM JOB !!!").await
+}
+
+async fn check_workday(state: &AppData, ctx: &RunContext) -> Result<(), anyhow::Error> {
+    if state.config.workday.is_empty() {
+        ctx.log("no workday sites configured");
+        return Ok(());
+    }
+
+    let client = &reqwest::Client::new();
+    check_feeds(
+        state,
+        ctx,
+        "workday site",
+        &state.config.workday,
+        |site| async move {
+            let found = workday::fetch(client, site).await?;
+            let got_everything = site.got_everything(found.len());
+            Ok((found, got_everything))
+        },
+        |found| format!("Found {} Workday jobs", found),
+    )
+    .await
+}
+
+async fn check_job_boards(state: &AppData, ctx: &RunContext) -> Result<(), anyhow::Error> {
+    if state.config.job_boards.is_empty() {
+        ctx.log("no job boards configured");
+        return Ok(());
+    }
+
+    let client = &reqwest::Client::new();
+    check_feeds(
+        state,
+        ctx,
+        "job board",
+        &state.config.job_boards,
+        // boards always list every opening
+        |board| async move { Ok((boards::fetch(client, board).await?, true)) },
+        |found| format!("Found {} jobs on Greenhouse and Lever", found),
+    )
+    .await
+}
+
+/// A site or board `check_feeds` goes through.
+trait Feed {
+    fn name(&self) -> &str;
+    /// Where its postings are tracked
+    fn feed(&self) -> String;
+    /// Whether a posting is worth reporting, checked after the whole feed is tracked
+    fn wanted(&self, _posting: &JobPosting) -> bool {
+        true
+    }
+}
+
+impl Feed for WorkdayConfig {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn feed(&self) -> String {
+        WorkdayConfig::feed(self)
+    }
+}
+
+impl Feed for JobBoardConfig {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn feed(&self) -> String {
+        JobBoardConfig::feed(self)
+    }
+
+    fn wanted(&self, posting: &JobPosting) -> bool {
+        JobBoardConfig::wanted(self, posting)
+    }
+}
+
+/// Fetches every feed, tracks what it has and reports the wanted jobs with
+/// `subject`, keeping going through the others when one fails. `fetch` returns
+/// a feed's postings and whether that's all of them.
+async fn check_feeds<'a, T, F, Fut>(
+    state: &AppData,
+    ctx: &RunContext,
+    kind: &str,
+    feeds: &'a [T],
+    fetch: F,
+    subject: impl Fn(usize) -> String,
+) -> Result<(), anyhow::Error>
+where
+    T: Feed,
+    F: Fn(&'a T) -> Fut,
+    Fut: Future<Output = Result<(Vec<JobPosting>, bool), anyhow::Error>>,
+{
+    let mut postings = Vec::new();
+    let mut updates = Vec::new();
+    let mut failed = Vec::new();
+    for feed in feeds {
+        match fetch(feed).await {
+            Ok((found, got_everything)) => {
+                ctx.log(format!("found {} jobs on {} '{}'", found.len(), kind, feed.name()));
+                let (changed, feed_updates) =
+                    track(state, ctx, &feed.feed(), &found, got_everything).await?;
+                updates.extend(feed_updates);
+                let total = found.len();
+                let wanted: Vec<_> = found.into_iter().filter(|p| feed.wanted(p)).collect();
+                if wanted.len() < total {
+                    ctx.log(format!(
+                        "kept {} of {} jobs on {} '{}'",
+                        wanted.len(),
+                        total,
+                        kind,
+                        feed.name()
+                    ));
+                }
+                let new: Vec<_> = wanted
+                    .into_iter()
+                    .filter(|posting| changed.contains(&posting.key))
+                    .collect();
+                ctx.log(format!(
+                    "{} new or changed jobs on {} '{}'",
+                    new.len(),
+                    kind,
+                    feed.name()
+                ));
+                postings.extend(new);
+            }
+            Err(e) => {
+                ctx.log(format!("{} '{}' failed: {:#}", kind, feed.name(), e));
+                failed.push(feed.name());
+            }
+        }
+    }
+
+    notify_updates(state, &updates).await?;
+    if !postings.is_empty() {
+        let subject = subject(postings.len());
+        report_jobs(state, ctx, postings, &subject).await?;
+    }
+
+    if !failed.is_empty() {
+        return Err(anyhow::anyhow!(
+            "failed to check {}(s): {}",
+            kind,
+            failed.join(", ")
+        ));
+    }
+    Ok(())
+}
+
+/// Stores a feed's latest poll, returning the keys of new or changed postings
+/// and what changed about saved postings. Postings are only closed when
+/// `got_everything`. Dry runs don't store anything, so every posting counts as new.
+async fn track(
+    state: &AppData,
+    ctx: &RunContext,
+    feed: &str,
+    postings: &[JobPosting],
+    got_everything: bool,
+) -> Result<(HashSet<String>, Vec<PostingUpdate>), anyhow::Error> {
+    if ctx.dry_run {
+        let keys = postings.iter().map(|posting| posting.key.clone()).collect();
+        return Ok((keys, Vec::new()));
+    }
+    if !got_everything {
+        ctx.log(format!(
+            "{} may have more jobs than were fetched, not closing any",
+            feed
+        ));
+    }
+
+    let (changed, updates) =
+        job_checker::track_feed(&state.db, feed, postings, got_everything).await?;
+    for update in &updates {
+        let fields: Vec<&str> = update.revisions.iter().map(|r| r.field.as_str()).collect();
+        ctx.log(format!(
+            "saved job {} changed: {}",
+            update.posting.key,
+            fields.join(", ")
+        ));
+    }
+    Ok((changed, updates))
+}
+
+async fn notify_updates(state: &AppData, updates: &[PostingUpdate]) -> Result<(), anyhow::Error> {
+    if updates.is_empty() {
+        return Ok(());
+    }
+
+    email::send_email(
+        &state.resend,
+        &job_checker::updates_html(updates),
+        Some(format!("{} saved jobs changed", updates.len()).as_str()),
+        None,
+    )
+    .await?;
+    Ok(())
+}
+
+/// Emails about saved jobs closing within `closing_soon_days` and
+/// applications that haven't moved in `stale_after_days`, each only once.
+async fn send_job_reminders(state: &AppData, ctx: &RunContext) -> Result<(), anyhow::Error> {
+    let days = Days::new(state.config.tracking.closing_soon_days.into());
+    let by = Utc::now().date_naive() + days;
+    let closing = job_store::saved_closing_by(&state.db, by).await?;
+
+    let stale_days = Days::new(state.config.applications.stale_after_days.into());
+    let stale = job_store::stale_applications(&state.db, Utc::now() - stale_days).await?;
+
+    if closing.is_empty() && stale.is_empty() {
+        ctx.log("no saved jobs closing soon or applications gone quiet");
+        return Ok(());
+    }
+
+    for posting in &closing {
+        ctx.log(format!(
+            "{} closes {}",
+            posting.key,
+            posting.deadline.as_deref().unwrap_or_default()
+        ));
+    }
+    for posting in &stale {
+        ctx.log(format!(
+            "{} still {} since {}",
+            posting.key,
+            posting.status.as_deref().unwrap_or_default(),
+            posting.status_changed_at.as_deref().unwrap_or_default()
+        ));
+    }
+
+    if ctx.dry_run {
+        ctx.log("dry run, not sending the reminder emails");
+        return Ok(());
+    }
+
+    if !closing.is_empty() {
+        email::send_email(
+            &state.resend,
+            &job_checker::closing_html(&closing),
+            Some(format!("{} saved jobs closing soon", closing.len()).as_str()),
+            None,
+        )
+        .await?;
+        job_store::mark_closing_notified(&state.db, &closing).await?;
+    }
+    if !stale.is_empty() {
+        email::send_email(
+            &state.resend,
+            &job_checker::stale_html(&stale),
+            Some(format!("{} applications haven't heard back", stale.len()).as_str()),
+            None,
+        )
+        .await?;
+        job_store::mark_stale_notified(&state.db, &stale).await?;
+    }
+    Ok(())
+}
+
+/// Drops the jobs hide rules and the filters rule out, merges postings of the
+/// same job, with each other and with what was seen before, and emails the
+/// result. Dry runs only merge within the batch and don't store the analyses.
+async fn report_jobs(
+    state: &AppData,
+    ctx: &RunContext,
+    postings: Vec<JobPosting>,
+    subject: &str,
+) -> Result<(), anyhow::Error> {
+    let analyses = job_checker::analyze_postings(
+        &state.db,
+        &state.config.analysis,
+        &postings,
+        !ctx.dry_run,
+    )
+    .await?;
+    let rules = job_store::rules(&state.db).await?;
+    let found = postings.len();
+    let postings = job_checker::filter_postings(postings, &analyses, &state.config.filters, &rules);
+    if postings.len() < found {
+        ctx.log(format!(
+            "filtered out {} of {} jobs",
+            found - postings.len(),
+            found
+        ));
+    }
+    if postings.is_empty() {
+        return Ok(());
+    }
+
+    // the same posting can come back under differently tracked urls, and may
+    // already have shown up in an alert email
+    let jobs = if ctx.dry_run {
+        job_checker::merge_batch(postings, &state.config.matching)
+    } else {
+        job_checker::merge_with_seen(&state.db, &state.config.matching, postings).await?
     };
 
-    Monitor::new().register({
-        WorkerBuilder::new("morning-cereal")
-            .data(cron_service_ext)
-            .retry(RetryPolicy::retries(5))
-            .backend(CronStream::new_with_timezone(schedule, Local))
-            .build_fn(say_hello_world)
-    })
+    ctx.log(format!("found {} jobs", jobs.len()));
+    for job in &jobs {
+        let links: Vec<&str> = job.postings.iter().map(|p| p.link.as_str()).collect();
+        ctx.log(format!("{} ({}) {}", job.title, job.location, links.join(" ")));
+    }
+
+    if ctx.dry_run {
+        ctx.log("dry run, not sending the jobs email");
+        return Ok(());
+    }
+
+    replies::send_digest(state, &jobs, subject, None).await?;
+
+    Ok(())
+}
+
+async fn health_check(state: &AppData, ctx: &RunContext) -> Result<(), anyhow::Error> {
+    sqlx::query("SELECT 1").execute(&state.db).await?;
+
+    let dead_letters = state.queue.dead_letters().await?;
+    if !dead_letters.is_empty() {
+        return Err(anyhow::anyhow!(
+            "{} webhook(s) waiting in the dead letter queue",
+            dead_letters.len()
+        ));
+    }
+
+    ctx.log("health check passed");
+    Ok(())
+}
+
+async fn poll_mailboxes(state: &AppData, ctx: &RunContext) -> Result<(), anyhow::Error> {
+    if state.mailboxes.is_empty() {
+        ctx.log("no mailboxes configured");
+        return Ok(());
+    }
+
+    // keep going through the other mailboxes when one fails
+    let mut failed = Vec::new();
+    for mailbox in &state.mailboxes {
+        if let Err(e) = poll_mailbox(state, mailbox, ctx).await {
+            ctx.log(format!("mailbox '{}' failed: {:#}", mailbox.config.name, e));
+            failed.push(mailbox.config.name.as_str());
+        }
+    }
+
+    if !failed.is_empty() {
+        return Err(anyhow::anyhow!(
+            "failed to poll mailbox(es): {}",
+            failed.join(", ")
+        ));
+    }
+    Ok(())
+}
+
+async fn poll_mailbox(
+    state: &AppData,
+    mailbox: &Mailbox,
+    ctx: &RunContext,
+) -> Result<(), anyhow::Error> {
+    let name = &mailbox.config.name;
+    let mut session = mailbox.connect().await?;
+    let (emails, unreadable) = session.fetch_new().await?;
+    ctx.log(format!("found {} message(s) in mailbox '{}'", emails.len(), name));
+    if !unreadable.is_empty() {
+        let uids: Vec<String> = unreadable.iter().map(u32::to_string).collect();
+        ctx.log(format!(
+            "couldn't read message(s) {} in mailbox '{}', skipping them until a restart",
+            uids.join(", "),
+            name
+        ));
+    }
+
+    let mut handled = Vec::new();
+    for email in &emails {
+        let message_id = processed_emails::message_id(&email.raw);
+        if let Some(message_id) = &message_id {
+            if processed_emails::is_processed(&state.db, message_id).await? {
+                ctx.log(format!("{} was already processed, skipping", message_id));
+                handled.push(email.uid);
+                continue;
+            }
+        }
+
+        if ctx.dry_run {
+            ctx.log(format!(
+                "dry run, not queueing message {} ({})",
+                email.uid,
+                message_id.as_deref().unwrap_or("no message id")
+            ));
+            continue;
+        }
+
+        let payload = EmailWebhookData {
+            from: email.sender().unwrap_or_default(),
+            to: name.clone(),
+            size: email.raw.len() as f64,
+            raw_content: STANDARD.encode(&email.raw),
+        };
+        let mut headers = HeaderMap::new();
+        if let Ok(mailbox_name) = HeaderValue::from_str(name) {
+            headers.insert("x-imap-mailbox", mailbox_name);
+        }
+
+        match queue::accept_webhook(
+            state,
+            IMAP_ROUTE,
+            &headers,
+            &serde_json::to_vec(&payload)?,
+        )
+        .await
+        {
+            Ok(id) => {
+                ctx.log(format!("queued message {} as {}", email.uid, id));
+                handled.push(email.uid);
+            }
+            // left in the mailbox so the next poll picks it up again
+            Err(e) => ctx.log(format!("failed to queue message {}: {}", email.uid, e)),
+        }
+    }
+
+    if !ctx.dry_run {
+        session.mark_handled(&handled).await?;
+    }
+    session.logout().await;
+    Ok(())
+}
+
+/// Upper bound on catch-up runs for a single schedule, so a long outage on a
+/// frequent schedule doesn't turn into hundreds of back to back runs.
+const MAX_CATCH_UP_RUNS: usize = 50;
+
+/// Replays ticks missed while the server was down, according to each schedule's catch-up policy.
+pub async fn catch_up_missed_runs(shared_state: Arc<AppData>) {
+    let now = Utc::now();
+
+    for schedule in &shared_state.config.schedules {
+        if !schedule.enabled || schedule.catch_up == CatchUpPolicy::Skip {
+            continue;
+        }
+
+        let last_run = match cron_history::last_scheduled_run(&shared_state.db, &schedule.name).await
+        {
+            Ok(Some(last_run)) => last_run,
+            // never ran before, so there's nothing to have missed
+            Ok(None) => continue,
+            Err(e) => {
+                error!("Couldn't read run history for '{}': {:#}", schedule.name, e);
+                continue;
+            }
+        };
+
+        let (cron, timezone) = match (schedule.cron_schedule(), schedule.timezone()) {
+            (Ok(cron), Ok(timezone)) => (cron, timezone),
+            (Err(e), _) | (_, Err(e)) => {
+                error!("Couldn't catch up schedule '{}': {:#}", schedule.name, e);
+                continue;
+            }
+        };
+
+        let mut missed: Vec<DateTime<Utc>> = cron
+            .after(&last_run.with_timezone(&timezone))
+            .map(|tick| tick.with_timezone(&Utc))
+            .take_while(|tick| *tick < now)
+            .collect();
+
+        if missed.is_empty() {
+            continue;
+        }
+
+        // only the most recent ticks are worth running
+        let keep = match schedule.catch_up {
+            CatchUpPolicy::RunOnce => 1,
+            _ => MAX_CATCH_UP_RUNS,
+        };
+        let skipped = missed.len().saturating_sub(keep);
+        missed = missed.split_off(skipped);
+        if skipped > 0 && schedule.catch_up == CatchUpPolicy::RunAll {
+            warn!(
+                "Skipping {} older missed run(s) of '{}', catch-up runs at most {}",
+                skipped, schedule.name, MAX_CATCH_UP_RUNS
+            );
+        }
+
+        info!(
+            "Catching up {} missed run(s) of '{}' since {}",
+            missed.len(),
+            schedule.name,
+            last_run
+        );
+        for tick in missed {
+            // failures are already recorded and reported by run_recorded
+            let ctx = RunContext::default();
+            let _ = run_recorded(schedule, &shared_state, tick, RunTrigger::CatchUp, &ctx).await;
+        }
+    }
+}
+
+pub fn build_cron_worker_monitor(shared_state: Arc<AppData>) -> Monitor {
+    let mut monitor = Monitor::new();
+
+    for schedule in &shared_state.config.schedules {
+        if !schedule.enabled {
+            info!("Schedule '{}' is disabled, skipping", schedule.name);
+            continue;
+        }
+
+        // config is validated on load, so these only fail if that was skipped
+        let (cron, timezone) = match (schedule.cron_schedule(), schedule.timezone()) {
+            (Ok(cron), Ok(timezone)) => (cron, timezone),
+            (Err(e), _) | (_, Err(e)) => {
+                error!("Couldn't register schedule '{}': {:#}", schedule.name, e);
+                continue;
+            }
+        };
+
+        info!(
+            "Registering schedule '{}' ({:?}) with cron '{}' in {}",
+            schedule.name, schedule.task, schedule.cron, timezone
+        );
+        let cron_service_ext = CronjobData {
+            schedule: schedule.clone(),
+            app_data: shared_state.clone(),
+        };
+
+        monitor = monitor.register({
+            WorkerBuilder::new(&schedule.name)
+                .data(cron_service_ext)
+                .retry(RetryPolicy::retries(schedule.retries))
+                .backend(CronStream::new_with_timezone(cron, timezone))
+                .build_fn(run_scheduled_job)
+        });
+    }
+
+    monitor
 }
@@ -1,16 +1,21 @@
 use axum::{
+    body::Bytes,
     extract::State,
-    // middleware,
+    http::{HeaderMap, StatusCode},
+    middleware,
     routing::{get, post},
     Json, Router,
 };
-use base64::{engine::general_purpose::STANDARD, Engine as _};
-use resend_rs::types::Attachment;
+use serde_json::{json, Value};
 use std::sync::Arc;
-use tracing::{error, info};
+use tracing::info;
 
 use crate::{
-    AppData, helpers::{email, job_checker::{self, JobAlertEmailHandler}}, models::job::{EmailWebhookData, optum::Job}
+    admin,
+    error::AppError,
+    middlewares,
+    queue::{self, JOB_ALERT_ROUTE},
+    AppData,
 };
 
 
@@ -23,14 +28,23 @@ pub fn build_router(
     let mut router: Router = Router::new()
         .route("/", get(hello_world))
         .route(
-            "/cloudflare-job-alert-reciever",
-            post(cloudflare_job_alert_reciever),
+            JOB_ALERT_ROUTE,
+            post(cloudflare_job_alert_reciever).route_layer(middleware::from_fn_with_state(
+                shared_state.clone(),
+                middlewares::verify_worker_signature,
+            )),
         )
         // test routes ----------------
         // .route("/notion-test", get(notion_test))
         // ----------------------------
         // .route("/notion-db", get(notion_db))
-        .route("/test", get(test))
+        .nest(
+            "/admin",
+            admin::router().route_layer(middleware::from_fn_with_state(
+                shared_state.clone(),
+                middlewares::admin_auth,
+            )),
+        )
         // .route("/notion-hook", post(notion_webhook))
         // .route_layer(middleware::from_fn_with_state(
         //     shared_state.clone(),
@@ -55,70 +69,17 @@ async fn hello_world() -> &'static str {
 
 async fn cloudflare_job_alert_reciever(
     State(state): State<Arc<AppData>>,
-    Json(payload): Json<EmailWebhookData>,
-) -> String {
-    info!("WEBHOOK RECEIVED: /cloudflare-job-alert-reciever");
-    info!(
-        "From: {}, To: {}, Size: {}",
-        &payload.from, &payload.to, &payload.size
-    );
-
-    // Decode the email content
-    match STANDARD.decode(&payload.raw_content) {
-        Ok(email_bytes) => match String::from_utf8(email_bytes.clone()) {
-            Ok(email_content) => {
-                info!(
-                    "Email content: {}",
-                    email_content.chars().take(200).collect::<String>()
-                );
-
-                let handler = JobAlertEmailHandler::new(&email_bytes);
-                let found_jobs = handler.results();
-
-                // Convert the HashMap to a formatted string for email
-                let job_content = if found_jobs.is_empty() {
-                    "No jobs found in email".to_string()
-                } else {
-                    let mut content = String::from("<h2>Found Jobs</h2>");
-                    for (job_title, job_url) in found_jobs.iter() {
-                        content.push_str(&format!(
-                            "<p><strong>{}</strong>: <a href=\"{}\">{}</a></p>",
-                            job_title, job_url, job_url
-                        ));
-                    }
-                    content
-                };
-
-                let subject = format!("Job alert processing from {}", payload.from);
-                email::send_email(
-                    &state.resend,
-                    &job_content,
-                    Some(subject.as_str()),
-                    Some(
-                        Attachment::from_content(email_bytes.clone())
-                            .with_content_type("txt")
-                            .with_filename("email_bytes"),
-                    ),
-                )
-                .await
-                .unwrap();
-            }
-            Err(e) => error!("Failed to parse email as UTF-8: {:?}", e),
-        },
-        Err(e) => error!("Failed to decode base64 content: {:?}", e),
-    }
-
-    // Send notification...
-    "Thank you for your webhook!".to_string()
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<(StatusCode, Json<Value>), AppError> {
+    info!("WEBHOOK RECEIVED: {}", JOB_ALERT_ROUTE);
+
+    let id = queue::accept_webhook(&state, JOB_ALERT_ROUTE, &headers, &body).await?;
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(json!({
+            "status": "queued",
+            "id": id,
+        })),
+    ))
 }
-
-async fn test() -> axum::Json<Vec<Job>> {
-    let jobs = job_checker::scheduler::optum().await.unwrap();
-    // let mut res: Vec<String> = vec![];
-
-    // for ele in jobs {
-    //     res.push(ele.to_string());
-    // }
-
-    axum::Json(jobs)
-}
\ No newline at end of file
@@ -0,0 +1,329 @@
+use std::{sync::Arc, time::Duration};
+
+use axum::http::{HeaderMap, HeaderValue};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+    time::timeout,
+};
+use tracing::{error, info, warn};
+
+use crate::{
+    config::{MailProtocol, SmtpConfig},
+    models::job::EmailWebhookData,
+    queue::{self, SMTP_ROUTE},
+    AppData,
+};
+
+/// RFC 5321 asks for at least 5 minutes between commands.
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+const MAX_LINE_BYTES: u64 = 4096;
+const MAX_RECIPIENTS: usize = 100;
+
+/// Minimal SMTP/LMTP receiver that hands every accepted message to the same
+/// archive and queue path as the Cloudflare webhook.
+pub struct MailListener {
+    config: SmtpConfig,
+    state: Arc<AppData>,
+}
+
+impl MailListener {
+    pub fn new(config: SmtpConfig, state: Arc<AppData>) -> Self {
+        Self { config, state }
+    }
+
+    pub async fn run(self) -> Result<(), anyhow::Error> {
+        let addr = self.config.bind_addr()?;
+        let listener = TcpListener::bind(addr).await?;
+        info!(
+            "Listening for {:?} on {}, accepting mail for {}",
+            self.config.protocol,
+            addr,
+            self.config.recipients.join(", ")
+        );
+
+        let config = Arc::new(self.config);
+        loop {
+            let (stream, peer) = match listener.accept().await {
+                Ok(connection) => connection,
+                Err(e) => {
+                    warn!("Failed to accept mail connection: {}", e);
+                    continue;
+                }
+            };
+
+            let config = config.clone();
+            let state = self.state.clone();
+            tokio::spawn(async move {
+                let peer = peer.to_string();
+                if let Err(e) = Session::new(&config, &state, &peer).serve(stream).await {
+                    warn!("Mail session with {} ended: {:#}", peer, e);
+                }
+            });
+        }
+    }
+}
+
+struct Session<'a> {
+    config: &'a SmtpConfig,
+    state: &'a AppData,
+    peer: &'a str,
+    greeted: bool,
+    mail_from: Option<String>,
+    recipients: Vec<String>,
+}
+
+impl<'a> Session<'a> {
+    fn new(config: &'a SmtpConfig, state: &'a AppData, peer: &'a str) -> Self {
+        Self {
+            config,
+            state,
+            peer,
+            greeted: false,
+            mail_from: None,
+            recipients: Vec::new(),
+        }
+    }
+
+    fn reset(&mut self) {
+        self.mail_from = None;
+        self.recipients.clear();
+    }
+
+    async fn serve(mut self, stream: TcpStream) -> Result<(), anyhow::Error> {
+        let (reader, mut writer) = stream.into_split();
+        let mut reader = BufReader::new(reader);
+
+        let greeting = match self.config.protocol {
+            MailProtocol::Smtp => "ESMTP",
+            MailProtocol::Lmtp => "LMTP",
+        };
+        reply(
+            &mut writer,
+            &format!("220 {} {} ready", self.config.hostname, greeting),
+        )
+        .await?;
+
+        loop {
+            let Some(line) = read_line(&mut reader).await? else {
+                return Ok(());
+            };
+            // the rest of an overlong command would otherwise be read as the next one
+            if !line.ends_with(b"\n") {
+                if !skip_rest_of_line(&mut reader).await? {
+                    return Ok(());
+                }
+                reply(&mut writer, "500 5.5.6 Line too long").await?;
+                continue;
+            }
+            let line = String::from_utf8_lossy(&line);
+            let line = line.trim_end_matches(['\r', '\n']);
+            let (verb, args) = line.split_once(' ').unwrap_or((line, ""));
+
+            let response = match verb.to_ascii_uppercase().as_str() {
+                "HELO" | "EHLO" if self.config.protocol == MailProtocol::Smtp => {
+                    self.reset();
+                    self.greeted = true;
+                    self.hello(verb.eq_ignore_ascii_case("EHLO"))
+                }
+                "LHLO" if self.config.protocol == MailProtocol::Lmtp => {
+                    self.reset();
+                    self.greeted = true;
+                    self.hello(true)
+                }
+                "MAIL" => self.mail(args),
+                "RCPT" => self.rcpt(args),
+                "DATA" => {
+                    if self.mail_from.is_none() || self.recipients.is_empty() {
+                        "503 5.5.1 Need MAIL and RCPT first".to_string()
+                    } else {
+                        reply(&mut writer, "354 End data with <CR><LF>.<CR><LF>").await?;
+                        self.data(&mut reader).await?
+                    }
+                }
+                "RSET" => {
+                    self.reset();
+                    "250 2.0.0 Ok".to_string()
+                }
+                "NOOP" => "250 2.0.0 Ok".to_string(),
+                "VRFY" => "252 2.1.5 Cannot verify".to_string(),
+                "QUIT" => {
+                    reply(&mut writer, "221 2.0.0 Bye").await?;
+                    return Ok(());
+                }
+                _ => "502 5.5.2 Command not recognized".to_string(),
+            };
+
+            reply(&mut writer, &response).await?;
+        }
+    }
+
+    fn hello(&self, extended: bool) -> String {
+        if extended {
+            format!(
+                "250-{}\r\n250-SIZE {}\r\n250 8BITMIME",
+                self.config.hostname, self.config.max_message_bytes
+            )
+        } else {
+            format!("250 {}", self.config.hostname)
+        }
+    }
+
+    fn mail(&mut self, args: &str) -> String {
+        if !self.greeted {
+            return "503 5.5.1 Say hello first".to_string();
+        }
+        let Some(address) = parse_path(args, "FROM:") else {
+            return "501 5.5.4 Syntax: MAIL FROM:<address>".to_string();
+        };
+        self.reset();
+        self.mail_from = Some(address);
+        "250 2.1.0 Ok".to_string()
+    }
+
+    fn rcpt(&mut self, args: &str) -> String {
+        if self.mail_from.is_none() {
+            return "503 5.5.1 Need MAIL first".to_string();
+        }
+        let Some(address) = parse_path(args, "TO:") else {
+            return "501 5.5.4 Syntax: RCPT TO:<address>".to_string();
+        };
+        if !self.config.accepts(&address) {
+            info!("Refusing mail for {} from {}", address, self.peer);
+            return "550 5.1.1 No such recipient".to_string();
+        }
+        if self.recipients.len() >= MAX_RECIPIENTS {
+            return "452 4.5.3 Too many recipients".to_string();
+        }
+        self.recipients.push(address);
+        "250 2.1.5 Ok".to_string()
+    }
+
+    async fn data<R: AsyncRead + Unpin>(
+        &mut self,
+        reader: &mut BufReader<R>,
+    ) -> Result<String, anyhow::Error> {
+        let mut message = Vec::new();
+        let mut too_big = false;
+        let mut line_start = true;
+
+        loop {
+            let Some(line) = read_line(reader).await? else {
+                return Err(anyhow::anyhow!("connection closed during DATA"));
+            };
+            // overlong lines come in pieces, only the first one starts a line
+            let starts_line = line_start;
+            line_start = line.ends_with(b"\n");
+            if starts_line && (line == b".\r\n" || line == b".\n") {
+                break;
+            }
+            // undo dot stuffing
+            let line = if starts_line {
+                line.strip_prefix(b".").unwrap_or(&line)
+            } else {
+                &line
+            };
+            // keep reading to the end so the session stays in sync
+            if message.len() + line.len() > self.config.max_message_bytes {
+                too_big = true;
+                continue;
+            }
+            message.extend_from_slice(line);
+        }
+
+        let recipients = self.recipients.len();
+        let response = if too_big {
+            "552 5.3.4 Message too big".to_string()
+        } else {
+            self.ingest(message).await
+        };
+        self.reset();
+
+        // LMTP answers once per recipient, the message is queued once either way
+        Ok(match self.config.protocol {
+            MailProtocol::Smtp => response,
+            MailProtocol::Lmtp => vec![response; recipients].join("\r\n"),
+        })
+    }
+
+    async fn ingest(&self, message: Vec<u8>) -> String {
+        let from = self.mail_from.clone().unwrap_or_default();
+        let payload = EmailWebhookData {
+            to: self.recipients.join(", "),
+            size: message.len() as f64,
+            raw_content: STANDARD.encode(&message),
+            from,
+        };
+        let body = match serde_json::to_vec(&payload) {
+            Ok(body) => body,
+            Err(e) => {
+                error!("Failed to serialize mail from {}: {}", self.peer, e);
+                return "451 4.3.0 Temporary failure".to_string();
+            }
+        };
+
+        let mut headers = HeaderMap::new();
+        if let Ok(peer) = HeaderValue::from_str(self.peer) {
+            headers.insert("x-smtp-peer", peer);
+        }
+
+        info!("MAIL RECEIVED: {} from {}", payload.from, self.peer);
+        match queue::accept_webhook(self.state, SMTP_ROUTE, &headers, &body).await {
+            Ok(id) => format!("250 2.0.0 Ok: queued as {}", id),
+            Err(e) if e.status().is_server_error() => {
+                "451 4.3.0 Temporary failure, try again later".to_string()
+            }
+            Err(e) => format!("554 5.6.0 {}", e),
+        }
+    }
+}
+
+/// Reads one line, `None` once the client hangs up. Overlong lines come back in
+/// pieces rather than being buffered forever, only the last one ends in `\n`.
+async fn read_line<R: AsyncRead + Unpin>(
+    reader: &mut BufReader<R>,
+) -> Result<Option<Vec<u8>>, anyhow::Error> {
+    let mut line = Vec::new();
+    let read = timeout(
+        COMMAND_TIMEOUT,
+        (&mut *reader)
+            .take(MAX_LINE_BYTES)
+            .read_until(b'\n', &mut line),
+    )
+    .await
+    .map_err(|_| anyhow::anyhow!("timed out waiting for the client"))??;
+
+    Ok((read > 0).then_some(line))
+}
+
+/// Reads and drops what's left of an overlong line, false if the client hung
+/// up first.
+async fn skip_rest_of_line<R: AsyncRead + Unpin>(
+    reader: &mut BufReader<R>,
+) -> Result<bool, anyhow::Error> {
+    loop {
+        match read_line(reader).await? {
+            Some(piece) if piece.ends_with(b"\n") => return Ok(true),
+            Some(_) => continue,
+            None => return Ok(false),
+        }
+    }
+}
+
+async fn reply<W: AsyncWrite + Unpin>(writer: &mut W, response: &str) -> std::io::Result<()> {
+    writer.write_all(response.as_bytes()).await?;
+    writer.write_all(b"\r\n").await?;
+    writer.flush().await
+}
+
+/// Pulls the address out of `FROM:<a@b.c> SIZE=123`. An empty path is valid for bounces.
+fn parse_path(args: &str, prefix: &str) -> Option<String> {
+    let rest = args
+        .get(..prefix.len())
+        .filter(|head| head.eq_ignore_ascii_case(prefix))
+        .map(|_| args[prefix.len()..].trim_start())?;
+    let rest = rest.strip_prefix('<')?;
+    let end = rest.find('>')?;
+    Some(rest[..end].to_string())
+}